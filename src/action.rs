@@ -0,0 +1,14 @@
+/// Messages passed from components to the runtime (and back via `update`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// periodic tick, emitted by the runtime at the configured tick rate
+    Tick,
+    /// request a redraw at the next frame
+    Render,
+    /// terminal was resized to (columns, rows)
+    Resize(u16, u16),
+    /// run a shell command in the foreground, with the terminal handed over to it
+    Execute(String),
+    /// leave the event loop
+    Quit,
+}
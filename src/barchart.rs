@@ -14,48 +14,39 @@
 //! [examples readme]: https://github.com/ratatui/ratatui/blob/main/examples/README.md
 
 use color_eyre::Result;
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
+use crossterm::event::{KeyCode, KeyEventKind};
 use rand::{thread_rng, Rng};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
-    prelude::{Backend, CrosstermBackend, Terminal},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize},
     text::Line,
     widgets::{Bar, BarChart, BarGroup, Block},
     Frame,
 };
-use std::{
-    env::args,
-    io::{self, stdout, Stdout},
+use ratatui_examples::{
+    action::Action,
+    component::Component,
+    event::AppEvent,
+    runtime::Runtime,
+    script::{run_script, Script},
+    tui::{install_hooks, Tui, TuiOptions},
 };
+use std::env::args;
 
 fn main() -> Result<()> {
-    color_eyre::install()?;
-    //    let terminal = ratatui::init();
-    let terminal = init_tui()?;
-    let app_result = App::new().run(terminal);
-    restore_tui()?;
+    install_hooks()?;
+    let mut args: Vec<String> = args().skip(1).collect();
+    if let Some(script) = Script::take_arg(&mut args)? {
+        return run_script(&script, &mut App::new());
+    }
+    let options = TuiOptions::parse(args)?;
+    let mut tui = Tui::with_options(options)?;
+    let app_result = Runtime::new().run(&mut tui, &mut App::new());
+    tui.restore()?;
     app_result
 }
 
-pub fn init_tui() -> io::Result<Terminal<impl Backend>> {
-    stdout().execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    Terminal::new(CrosstermBackend::new(stdout()))
-}
-
-pub fn restore_tui() -> io::Result<()> {
-    stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
-    Ok(())
-}
-
 struct App {
-    should_exit: bool,
     temperatures: Vec<u8>,
 }
 
@@ -63,37 +54,33 @@ impl App {
     fn new() -> Self {
         let mut rng = thread_rng();
         let temperatures = (0..24).map(|_| rng.gen_range(50..90)).collect();
-        Self {
-            should_exit: false,
-            temperatures,
-        }
+        Self::with_temperatures(temperatures)
     }
 
-    fn run(mut self, mut terminal: Terminal<impl Backend>) -> Result<()> {
-        while !self.should_exit {
-            terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
-        }
-        Ok(())
+    /// One temperature per hour, starting at midnight.
+    fn with_temperatures(temperatures: Vec<u8>) -> Self {
+        Self { temperatures }
     }
+}
 
-    fn handle_events(&mut self) -> Result<()> {
-        if let Event::Key(key) = event::read()? {
+impl Component for App {
+    fn handle_event(&mut self, event: &AppEvent) -> Result<Option<Action>> {
+        if let AppEvent::Key(key) = event {
             if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('q') {
-                self.should_exit = true;
+                return Ok(Some(Action::Quit));
             }
         }
-        Ok(())
+        Ok(None)
     }
 
-    fn draw(&self, frame: &mut Frame) {
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
         let [title, vertical, horizontal] = Layout::vertical([
             Constraint::Length(1),
             Constraint::Fill(1),
             Constraint::Fill(1),
         ])
         .spacing(1)
-        .areas(frame.area());
+        .areas(area);
 
         frame.render_widget("Barchart".bold().into_centered_line(), title);
         frame.render_widget(vertical_barchart(&self.temperatures), vertical);
@@ -102,7 +89,7 @@ impl App {
 }
 
 /// Create a vertical bar chart from the temperatures data.
-fn vertical_barchart(temperatures: &[u8]) -> BarChart {
+fn vertical_barchart(temperatures: &[u8]) -> BarChart<'_> {
     let bars: Vec<Bar> = temperatures
         .iter()
         .enumerate()
@@ -115,7 +102,7 @@ fn vertical_barchart(temperatures: &[u8]) -> BarChart {
         .bar_width(5)
 }
 
-fn vertical_bar(hour: usize, temperature: &u8) -> Bar {
+fn vertical_bar(hour: usize, temperature: &u8) -> Bar<'_> {
     Bar::default()
         .value(u64::from(*temperature))
         .label(Line::from(format!("{hour:>02}:00")))
@@ -125,7 +112,7 @@ fn vertical_bar(hour: usize, temperature: &u8) -> Bar {
 }
 
 /// Create a horizontal bar chart from the temperatures data.
-fn horizontal_barchart(temperatures: &[u8]) -> BarChart {
+fn horizontal_barchart(temperatures: &[u8]) -> BarChart<'_> {
     let bars: Vec<Bar> = temperatures
         .iter()
         .enumerate()
@@ -140,7 +127,7 @@ fn horizontal_barchart(temperatures: &[u8]) -> BarChart {
         .direction(Direction::Horizontal)
 }
 
-fn horizontal_bar(hour: usize, temperature: &u8) -> Bar {
+fn horizontal_bar(hour: usize, temperature: &u8) -> Bar<'_> {
     let style = temperature_style(*temperature);
     Bar::default()
         .value(u64::from(*temperature))
@@ -156,3 +143,76 @@ fn temperature_style(value: u8) -> Style {
     let color = Color::Rgb(255, green, 0);
     Style::new().fg(color)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui_examples::script::Headless;
+
+    #[test]
+    fn temperature_style_boundaries() {
+        assert_eq!(
+            temperature_style(50),
+            Style::new().fg(Color::Rgb(255, 255, 0))
+        );
+        assert_eq!(
+            temperature_style(70),
+            Style::new().fg(Color::Rgb(255, 127, 0))
+        );
+        assert_eq!(
+            temperature_style(90),
+            Style::new().fg(Color::Rgb(255, 0, 0))
+        );
+    }
+
+    #[test]
+    fn quit_with_q() {
+        let mut app = App::with_temperatures(vec![60]);
+        let key = |c| AppEvent::Key(KeyCode::Char(c).into());
+        assert_eq!(app.handle_event(&key('x')).unwrap(), None);
+        assert_eq!(app.handle_event(&key('q')).unwrap(), Some(Action::Quit));
+    }
+
+    #[test]
+    fn render_snapshot() {
+        let mut app = App::with_temperatures(vec![50, 70, 90]);
+        let screen = Headless::new(24, 15).unwrap().screen(&mut app).unwrap();
+        assert_eq!(screen.lines(), SNAPSHOT);
+        // bars are colored by temperature
+        assert_eq!(screen.buffer[(0, 5)].fg, Color::Rgb(255, 255, 0));
+        assert_eq!(screen.buffer[(23, 12)].fg, Color::Rgb(255, 0, 0));
+    }
+
+    const SNAPSHOT: [&str; 15] = [
+        "        Barchart        ",
+        "                        ",
+        "   Weather (Vertical)   ",
+        "            █████       ",
+        "▁▁▁▁▁ █████ █████       ",
+        "█████ █████ █████       ",
+        " 50°█  70°█  90°█       ",
+        "00:00 01:00 02:00       ",
+        "                        ",
+        "  Weather (Horizontal)  ",
+        "00:00  50°██████        ",
+        "01:00  70°██████████    ",
+        "02:00  90°██████████████",
+        "                        ",
+        "                        ",
+    ];
+
+    #[test]
+    fn render_at_several_sizes() {
+        let mut app = App::with_temperatures(vec![55; 24]);
+        // too small for anything but must not panic
+        Headless::new(1, 1).unwrap().screen(&mut app).unwrap();
+        for (width, height) in [(10, 5), (80, 24), (200, 60)] {
+            let screen = Headless::new(width, height)
+                .unwrap()
+                .screen(&mut app)
+                .unwrap();
+            assert_eq!(screen.buffer.area, Rect::new(0, 0, width, height));
+            assert!(screen.lines()[0].contains("Barchart"));
+        }
+    }
+}
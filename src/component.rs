@@ -0,0 +1,26 @@
+use crate::{action::Action, event::AppEvent};
+use color_eyre::Result;
+use ratatui::{layout::Rect, Frame};
+
+/// A screen (or part of one) driven by the [`Runtime`](crate::runtime::Runtime).
+///
+/// Events are mapped to actions in `handle_event`, actions change state in
+/// `update`, and `render` draws the current state. Returning an action from
+/// either of the first two queues it for the runtime.
+pub trait Component {
+    fn handle_event(&mut self, _event: &AppEvent) -> Result<Option<Action>> {
+        Ok(None)
+    }
+
+    fn update(&mut self, _action: Action) -> Result<Option<Action>> {
+        Ok(None)
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect);
+
+    /// Background work is pending that ticks will pick up, like a search
+    /// not answered yet. Headless runs tick until it is done.
+    fn is_busy(&self) -> bool {
+        false
+    }
+}
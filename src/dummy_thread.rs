@@ -1,4 +1,4 @@
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
 use std::{thread, time::Duration};
 
 pub struct Dummy {
@@ -19,13 +19,20 @@ impl Dummy {
             let mut progress_state: f64 = 0.0;
             while run {
                 let _ = dummy_obj.tx_status.send(progress_state);
-                progress_state = (progress_state + 0.5).min(100.0);
-                thread::sleep(Duration::from_millis(100));
-                if let Ok(close) = dummy_obj.rx_close.try_recv() {
-                    run = !close
+                progress_state = Self::advance(progress_state);
+                // wakes up as soon as closing is requested
+                match dummy_obj.rx_close.recv_timeout(Duration::from_millis(100)) {
+                    Ok(close) => run = !close,
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => run = false,
                 }
             }
         });
         (thread_join_handle, tx_close, rx_status)
     }
+
+    /// Progress in percent after one more step, sent every 100 ms.
+    pub fn advance(progress_state: f64) -> f64 {
+        (progress_state + 0.5).min(100.0)
+    }
 }
@@ -0,0 +1,138 @@
+use color_eyre::Result;
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use crossterm::event::{self, Event, KeyEvent, MouseEvent};
+use std::time::Duration;
+
+/// Input of a [`Component`](crate::component::Component), from the terminal or elsewhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+    /// terminal was resized to (columns, rows)
+    Resize(u16, u16),
+    /// a tick sent by the event source, besides the ones of the runtime
+    Tick,
+    /// an app specific message, like a line from a background task
+    Custom(String),
+}
+
+impl AppEvent {
+    /// The terminal events the apps handle, focus changes and pastes are dropped.
+    pub fn from_crossterm(event: Event) -> Option<Self> {
+        match event {
+            Event::Key(key) => Some(AppEvent::Key(key)),
+            Event::Mouse(mouse) => Some(AppEvent::Mouse(mouse)),
+            Event::Resize(columns, rows) => Some(AppEvent::Resize(columns, rows)),
+            Event::FocusGained | Event::FocusLost | Event::Paste(_) => None,
+        }
+    }
+}
+
+/// Where the [`Runtime`](crate::runtime::Runtime) gets its events from.
+pub trait EventSource {
+    /// The next event, or `None` if there was none within `timeout`.
+    fn next(&mut self, timeout: Duration) -> Result<Option<AppEvent>>;
+
+    /// No events will arrive anymore, the runtime quits.
+    fn is_closed(&self) -> bool {
+        false
+    }
+}
+
+/// Terminal events, waited for with `event::poll` so the caller is never
+/// blocked longer than the timeout.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn next(&mut self, timeout: Duration) -> Result<Option<AppEvent>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+        Ok(AppEvent::from_crossterm(event::read()?))
+    }
+}
+
+/// Events sent through a channel, by tests or other threads.
+///
+/// The source is closed once all senders are dropped.
+#[derive(Debug, Clone)]
+pub struct ChannelEvents {
+    rx: Receiver<AppEvent>,
+    closed: bool,
+}
+
+impl ChannelEvents {
+    pub fn new() -> (Sender<AppEvent>, Self) {
+        let (tx, rx) = unbounded();
+        (tx, Self::from_receiver(rx))
+    }
+
+    pub fn from_receiver(rx: Receiver<AppEvent>) -> Self {
+        Self { rx, closed: false }
+    }
+}
+
+impl EventSource for ChannelEvents {
+    fn next(&mut self, timeout: Duration) -> Result<Option<AppEvent>> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(event) => Ok(Some(event)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                self.closed = true;
+                Ok(None)
+            }
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyCode;
+    use std::time::Instant;
+
+    #[test]
+    fn channel_events_in_order() {
+        let (tx, mut events) = ChannelEvents::new();
+        let key = AppEvent::Key(KeyCode::Char('q').into());
+        tx.send(AppEvent::Custom("hello".to_string())).unwrap();
+        tx.send(key.clone()).unwrap();
+        let timeout = Duration::from_millis(10);
+        assert_eq!(
+            events.next(timeout).unwrap(),
+            Some(AppEvent::Custom("hello".to_string()))
+        );
+        assert_eq!(events.next(timeout).unwrap(), Some(key));
+        assert_eq!(events.next(timeout).unwrap(), None);
+        assert!(!events.is_closed());
+    }
+
+    #[test]
+    fn channel_closes_when_senders_are_dropped() {
+        let (tx, mut events) = ChannelEvents::new();
+        tx.send(AppEvent::Tick).unwrap();
+        drop(tx);
+        let started = Instant::now();
+        assert_eq!(
+            events.next(Duration::from_secs(5)).unwrap(),
+            Some(AppEvent::Tick)
+        );
+        assert_eq!(events.next(Duration::from_secs(5)).unwrap(), None);
+        assert!(events.is_closed());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn only_handled_terminal_events_are_converted() {
+        assert_eq!(
+            AppEvent::from_crossterm(Event::Resize(80, 24)),
+            Some(AppEvent::Resize(80, 24))
+        );
+        assert_eq!(AppEvent::from_crossterm(Event::FocusLost), None);
+    }
+}
@@ -0,0 +1,17 @@
+//! Building blocks of the `fuzzy_finder` example.
+
+pub mod config;
+pub mod fields;
+pub mod frecency;
+pub mod history;
+pub mod item_store;
+pub mod keymap;
+pub mod line_editor;
+pub mod matcher;
+pub mod normalize;
+pub mod options;
+pub mod preview;
+pub mod query;
+pub mod source;
+pub mod theme;
+pub mod worker;
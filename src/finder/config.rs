@@ -0,0 +1,73 @@
+use crate::finder::{keymap::Keymap, theme::Theme};
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+
+/// `$XDG_CONFIG_HOME/ratatui_examples/fuzzy_finder.toml` on Linux.
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("ratatui_examples").join("fuzzy_finder.toml"))
+}
+
+/// Settings of the config file, on top of the defaults.
+///
+/// ```toml
+/// [bindings]
+/// ctrl-j = "down"
+///
+/// [colors]
+/// preset = "light"
+/// hl = "red:bold"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub keymap: Keymap,
+    pub theme: Theme,
+}
+
+impl Config {
+    /// Load `path`, or the default config file if there is one.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match config_path() {
+                Some(path) => (path, false),
+                None => return Self::parse(""),
+            },
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if !required && err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("reading {}", path.display()));
+            }
+        };
+        Self::parse(&content).wrap_err_with(|| format!("invalid config {}", path.display()))
+    }
+
+    /// Parse a config file, colors start from [`Theme::from_env`].
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut config = Self {
+            keymap: Keymap::default(),
+            theme: Theme::from_env(),
+        };
+        let table: Table = content.parse()?;
+        for (section, value) in table {
+            let Value::Table(value) = value else {
+                bail!("unknown setting {section}, expected a [bindings] or [colors] table");
+            };
+            match section.as_str() {
+                "bindings" => config.keymap.bind_toml(value)?,
+                "colors" => config.theme.apply_toml(value)?,
+                _ => bail!("unknown section [{section}], expected [bindings] or [colors]"),
+            }
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,314 @@
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Report, Result,
+};
+use regex::Regex;
+use std::{borrow::Cow, ops::Range, str::FromStr, sync::LazyLock};
+
+/// a field with its trailing whitespace, leading whitespace belongs to the first field
+static AWK_FIELD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\s*\S+\s*").expect("valid regex"));
+
+/// How a candidate is split into fields.
+#[derive(Debug, Clone, Default)]
+pub enum Delimiter {
+    /// AWK style, fields are separated by runs of whitespace
+    #[default]
+    Whitespace,
+    Regex(Regex),
+}
+
+impl PartialEq for Delimiter {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Delimiter::Whitespace, Delimiter::Whitespace) => true,
+            (Delimiter::Regex(a), Delimiter::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Delimiter {}
+
+impl FromStr for Delimiter {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            bail!("empty delimiter");
+        }
+        let regex = Regex::new(s).wrap_err_with(|| format!("invalid delimiter {s}"))?;
+        Ok(Delimiter::Regex(regex))
+    }
+}
+
+/// Field index or range like `2`, `-1`, `2..`, `..-2` or `1..3`.
+///
+/// Indices start at 1, negative ones count from the last field, both ends
+/// are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRange {
+    start: Option<isize>,
+    end: Option<isize>,
+}
+
+impl FieldRange {
+    /// Zero based indices of the fields covered, given `count` fields.
+    fn resolve(&self, count: usize) -> Range<usize> {
+        let index = |i: isize| match i {
+            i if i > 0 => (i - 1) as usize,
+            i => count.saturating_sub(i.unsigned_abs()),
+        };
+        let start = self.start.map_or(0, index);
+        let end = self.end.map_or(count, |i| {
+            // an end before the first field selects nothing
+            if i < 0 && i.unsigned_abs() > count {
+                0
+            } else {
+                index(i) + 1
+            }
+        });
+        start.min(count)..end.min(count).max(start.min(count))
+    }
+
+    /// Parse a comma separated list like `1,3..`.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>> {
+        s.split(',').map(str::parse).collect()
+    }
+}
+
+impl FromStr for FieldRange {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let index = |i: &str| -> Result<Option<isize>> {
+            if i.is_empty() {
+                return Ok(None);
+            }
+            match i.parse() {
+                Ok(0) | Err(_) => bail!("invalid field index {i} in {s}, fields start at 1"),
+                Ok(i) => Ok(Some(i)),
+            }
+        };
+        match s.split_once("..") {
+            Some((start, end)) => Ok(Self {
+                start: index(start)?,
+                end: index(end)?,
+            }),
+            None if s.is_empty() => bail!("empty field index"),
+            None => {
+                let i = index(s)?;
+                Ok(Self { start: i, end: i })
+            }
+        }
+    }
+}
+
+/// A field of a candidate as byte offsets.
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    start: usize,
+    /// end without the trailing delimiter
+    content_end: usize,
+    end: usize,
+}
+
+/// `--delimiter`, `--nth`, `--with-nth` and `--accept-nth`: which fields of a
+/// candidate are matched, shown and printed on accept.
+///
+/// Empty field lists use the whole line. Selected fields are joined with the
+/// delimiters between them, only the one after the last field is dropped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fields {
+    pub delimiter: Delimiter,
+    pub nth: Vec<FieldRange>,
+    pub with_nth: Vec<FieldRange>,
+    pub accept_nth: Vec<FieldRange>,
+}
+
+impl Fields {
+    /// The text queries are matched against.
+    pub fn match_text<'a>(&self, item: &'a str) -> Cow<'a, str> {
+        self.select(item, &self.nth)
+    }
+
+    /// Map char indices into [`match_text`](Self::match_text) onto `item`,
+    /// sorted as `--nth 3,1` puts later fields first.
+    pub fn item_indices(&self, item: &str, indices: Vec<usize>) -> Vec<usize> {
+        if self.nth.is_empty() {
+            return indices;
+        }
+        let chars = self.selected_chars(item, &self.nth);
+        let mut indices: Vec<usize> = indices
+            .into_iter()
+            .filter_map(|i| chars.get(i).copied())
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    /// The text shown in the result list.
+    pub fn display<'a>(&self, item: &'a str) -> Cow<'a, str> {
+        self.select(item, &self.with_nth)
+    }
+
+    /// Map sorted char indices into `item` onto [`display`](Self::display),
+    /// indices of hidden fields are dropped.
+    pub fn display_indices(&self, item: &str, indices: &[usize]) -> Vec<usize> {
+        if self.with_nth.is_empty() {
+            return indices.to_vec();
+        }
+        self.selected_chars(item, &self.with_nth)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, i)| indices.binary_search(i).is_ok())
+            .map(|(display_index, _)| display_index)
+            .collect()
+    }
+
+    /// The text printed when `item` is accepted.
+    pub fn accept(&self, item: &str) -> String {
+        self.select(item, &self.accept_nth).into_owned()
+    }
+
+    fn tokens(&self, item: &str) -> Vec<Token> {
+        match &self.delimiter {
+            Delimiter::Whitespace => AWK_FIELD
+                .find_iter(item)
+                .map(|field| Token {
+                    start: field.start(),
+                    content_end: field.start() + field.as_str().trim_end().len(),
+                    end: field.end(),
+                })
+                .collect(),
+            Delimiter::Regex(regex) => {
+                let mut tokens = Vec::new();
+                let mut start = 0;
+                for delimiter in regex.find_iter(item).filter(|m| !m.is_empty()) {
+                    tokens.push(Token {
+                        start,
+                        content_end: delimiter.start(),
+                        end: delimiter.end(),
+                    });
+                    start = delimiter.end();
+                }
+                if start < item.len() {
+                    tokens.push(Token {
+                        start,
+                        content_end: item.len(),
+                        end: item.len(),
+                    });
+                }
+                tokens
+            }
+        }
+    }
+
+    /// Byte ranges of `item` making up the selected fields, in selection order.
+    fn segments(&self, item: &str, ranges: &[FieldRange]) -> Vec<Range<usize>> {
+        let tokens = self.tokens(item);
+        let selected: Vec<Token> = ranges
+            .iter()
+            .flat_map(|range| range.resolve(tokens.len()))
+            .map(|i| tokens[i])
+            .collect();
+        let last = selected.len().saturating_sub(1);
+        selected
+            .iter()
+            .enumerate()
+            .map(|(i, token)| {
+                let end = if i == last {
+                    token.content_end
+                } else {
+                    token.end
+                };
+                token.start..end
+            })
+            .collect()
+    }
+
+    fn select<'a>(&self, item: &'a str, ranges: &[FieldRange]) -> Cow<'a, str> {
+        if ranges.is_empty() {
+            return Cow::Borrowed(item);
+        }
+        match self.segments(item, ranges).as_slice() {
+            [segment] => Cow::Borrowed(&item[segment.clone()]),
+            segments => Cow::Owned(segments.iter().map(|s| &item[s.clone()]).collect()),
+        }
+    }
+
+    /// Char index in `item` of every char of the selected fields.
+    fn selected_chars(&self, item: &str, ranges: &[FieldRange]) -> Vec<usize> {
+        let char_index = |byte: usize| item[..byte].chars().count();
+        self.segments(item, ranges)
+            .into_iter()
+            .flat_map(|segment| {
+                let start = char_index(segment.start);
+                start..start + item[segment].chars().count()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(delimiter: Option<&str>, nth: &str, with_nth: &str, accept_nth: &str) -> Fields {
+        let list = |s: &str| {
+            if s.is_empty() {
+                Vec::new()
+            } else {
+                FieldRange::parse_list(s).unwrap()
+            }
+        };
+        Fields {
+            delimiter: delimiter.map_or(Delimiter::Whitespace, |d| d.parse().unwrap()),
+            nth: list(nth),
+            with_nth: list(with_nth),
+            accept_nth: list(accept_nth),
+        }
+    }
+
+    #[test]
+    fn parse_field_ranges() {
+        let range = |start, end| FieldRange { start, end };
+        assert_eq!("2".parse::<FieldRange>().unwrap(), range(Some(2), Some(2)));
+        assert_eq!("2..".parse::<FieldRange>().unwrap(), range(Some(2), None));
+        assert_eq!("..-2".parse::<FieldRange>().unwrap(), range(None, Some(-2)));
+        assert_eq!(FieldRange::parse_list("1,3..").unwrap().len(), 2);
+        for invalid in ["0", "", "a", "1..0", "1,"] {
+            assert!(FieldRange::parse_list(invalid).is_err(), "{invalid}");
+        }
+        assert!("".parse::<Delimiter>().is_err());
+    }
+
+    #[test]
+    fn whitespace_fields_keep_the_delimiters_between_them() {
+        let item = "  a  bb ccc";
+        assert_eq!(fields(None, "2", "", "").match_text(item), "bb");
+        assert_eq!(fields(None, "-1", "", "").match_text(item), "ccc");
+        assert_eq!(fields(None, "", "2..", "").display(item), "bb ccc");
+        assert_eq!(fields(None, "", "", "1").accept(item), "  a");
+        // out of range fields select nothing
+        assert_eq!(fields(None, "5", "", "").match_text(item), "");
+        assert_eq!(fields(None, "..-5", "", "").match_text(item), "");
+        assert_eq!(fields(None, "", "", "").accept(item), item);
+    }
+
+    #[test]
+    fn indices_map_between_fields_and_item() {
+        let item = "  a  bb ccc";
+        let nth = fields(None, "3,1", "", "");
+        assert_eq!(nth.match_text(item), "ccc  a");
+        assert_eq!(nth.item_indices(item, vec![0, 5]), [2, 8]);
+
+        let records = fields(Some("\t"), "", "2..", "1");
+        let item = "id\tname\tpath";
+        assert_eq!(records.display(item), "name\tpath");
+        assert_eq!(records.accept(item), "id");
+        // the `i` of the hidden id is dropped
+        assert_eq!(records.display_indices(item, &[0, 3, 4]), [0, 1]);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::finder::history::data_dir;
+use color_eyre::{eyre::WrapErr, Result};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// entries kept on disk, the ones with the lowest frecency are dropped
+const MAX_ENTRIES: usize = 1000;
+/// added to the matcher score per frecency point
+const BOOST_PER_POINT: f64 = 5.0;
+const MAX_BOOST: i64 = 100;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Visit {
+    count: u32,
+    /// seconds since the unix epoch
+    last_access: u64,
+}
+
+impl Visit {
+    /// Accepted often and recently scores high, like zoxide and Firefox.
+    fn frecency(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_access);
+        let recency = match age {
+            age if age < HOUR => 4.0,
+            age if age < DAY => 2.0,
+            age if age < WEEK => 0.5,
+            _ => 0.25,
+        };
+        f64::from(self.count) * recency
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Accepted candidates with how often and when they were accepted.
+///
+/// Stored per source next to the query history as `count\tlast_access\titem` lines.
+#[derive(Debug, Default)]
+pub struct Frecency {
+    path: Option<PathBuf>,
+    visits: HashMap<String, Visit>,
+}
+
+impl Frecency {
+    pub fn load(namespace: &str) -> Result<Self> {
+        let Some(dir) = data_dir() else {
+            return Ok(Self::default());
+        };
+        Self::load_from(dir.join("frecency").join(namespace))
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("reading {}", path.display()));
+            }
+        };
+        // malformed lines are skipped instead of failing the whole finder
+        let visits = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let count = fields.next()?.parse().ok()?;
+                let last_access = fields.next()?.parse().ok()?;
+                let item = fields.next()?.to_string();
+                Some((item, Visit { count, last_access }))
+            })
+            .collect();
+        Ok(Self {
+            path: Some(path),
+            visits,
+        })
+    }
+
+    /// Count an accepted `item`.
+    pub fn record(&mut self, item: &str, now: SystemTime) {
+        let visit = self.visits.entry(item.to_string()).or_insert(Visit {
+            count: 0,
+            last_access: 0,
+        });
+        visit.count = visit.count.saturating_add(1);
+        visit.last_access = unix_seconds(now);
+    }
+
+    /// Frecency of every known item at `now`, for ranking results.
+    pub fn ranking(&self, now: SystemTime) -> Ranking {
+        let now = unix_seconds(now);
+        let scores = self
+            .visits
+            .iter()
+            .map(|(item, visit)| (item.clone(), visit.frecency(now)))
+            .collect();
+        Ranking { scores }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let now = unix_seconds(SystemTime::now());
+        let mut visits: Vec<(&String, &Visit)> = self.visits.iter().collect();
+        visits.sort_by(|(a_item, a), (b_item, b)| {
+            b.frecency(now)
+                .total_cmp(&a.frecency(now))
+                .then_with(|| a_item.cmp(b_item))
+        });
+        visits.truncate(MAX_ENTRIES);
+        let content: String = visits
+            .into_iter()
+            .map(|(item, visit)| format!("{}\t{}\t{item}\n", visit.count, visit.last_access))
+            .collect();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).wrap_err_with(|| format!("creating {}", dir.display()))?;
+        }
+        fs::write(path, content).wrap_err_with(|| format!("writing {}", path.display()))
+    }
+}
+
+/// Snapshot of frecency scores used to reorder match results.
+#[derive(Debug, Default, Clone)]
+pub struct Ranking {
+    scores: HashMap<String, f64>,
+}
+
+impl Ranking {
+    pub fn score(&self, item: &str) -> f64 {
+        self.scores.get(item).copied().unwrap_or(0.0)
+    }
+
+    /// Added to the matcher score of `item`, bounded so a frequently used
+    /// but poorly matching item can not outrank a good match.
+    pub fn boost(&self, item: &str) -> i64 {
+        ((self.score(item) * BOOST_PER_POINT) as i64).min(MAX_BOOST)
+    }
+
+    /// Orders more frecent items first, used for results with equal scores.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.score(b).total_cmp(&self.score(a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process, time::Duration};
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn recent_and_frequent_visits_score_higher() {
+        let visit = Visit {
+            count: 2,
+            last_access: 10 * WEEK,
+        };
+        let scores = [0, HOUR, DAY, WEEK].map(|age| visit.frecency(10 * WEEK + age));
+        assert_eq!(scores, [8.0, 4.0, 1.0, 0.5]);
+
+        let mut frecency = Frecency::default();
+        frecency.record("old", at(0));
+        frecency.record("once", at(WEEK));
+        frecency.record("twice", at(WEEK));
+        frecency.record("twice", at(WEEK));
+        let ranking = frecency.ranking(at(WEEK));
+        let scores = ["twice", "once", "old", "never"].map(|item| ranking.score(item));
+        assert_eq!(scores, [8.0, 4.0, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn boost_is_bounded() {
+        let mut frecency = Frecency::default();
+        frecency.record("once", at(0));
+        for _ in 0..100 {
+            frecency.record("often", at(0));
+        }
+        let ranking = frecency.ranking(at(0));
+        let boosts = ["once", "often", "never"].map(|item| ranking.boost(item));
+        assert_eq!(boosts, [20, MAX_BOOST, 0]);
+    }
+
+    #[test]
+    fn compare_puts_more_frecent_items_first() {
+        let mut frecency = Frecency::default();
+        frecency.record("a", at(0));
+        frecency.record("b", at(0));
+        frecency.record("b", at(0));
+        let ranking = frecency.ranking(at(0));
+        let mut items = ["x", "a", "b", "y"];
+        items.sort_by(|a, b| ranking.compare(a, b));
+        // unknown items tie, the stable sort keeps their order
+        assert_eq!(items, ["b", "a", "x", "y"]);
+    }
+
+    #[test]
+    fn save_and_load_skip_malformed_lines() {
+        let path = env::temp_dir().join(format!("ratatui_examples-frecency-{}", process::id()));
+        fs::write(
+            &path,
+            "2\t100\tsrc/main.rs\nnot a count\t1\tx\n1\t50\ta\tb\n",
+        )
+        .unwrap();
+        let mut frecency = Frecency::load_from(path.clone()).unwrap();
+        assert_eq!(frecency.visits.len(), 2);
+        assert_eq!(
+            frecency.visits["a\tb"],
+            Visit {
+                count: 1,
+                last_access: 50
+            }
+        );
+        frecency.record("src/main.rs", at(200));
+        frecency.save().unwrap();
+        let loaded = Frecency::load_from(path.clone()).unwrap();
+        assert_eq!(loaded.visits, frecency.visits);
+        let _ = fs::remove_file(path);
+    }
+}
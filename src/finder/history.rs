@@ -0,0 +1,216 @@
+use color_eyre::{eyre::WrapErr, Result};
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+pub const DEFAULT_HISTORY_SIZE: usize = 1000;
+
+/// Directory for persistent state, `$XDG_DATA_HOME/ratatui_examples` on Linux.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ratatui_examples"))
+}
+
+/// Previously submitted queries, oldest first.
+///
+/// Every candidate source has its own history file, so queries for
+/// `git branch` are not mixed up with queries for `find .`.
+#[derive(Debug, Default)]
+pub struct History {
+    /// `None` keeps the history in memory only
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+    max_size: usize,
+    /// entry currently recalled, `None` while editing a new query
+    position: Option<usize>,
+    /// query typed before recalling, restored when moving past the newest entry
+    draft: String,
+}
+
+impl History {
+    pub fn in_memory(max_size: usize) -> Self {
+        Self {
+            max_size,
+            ..Self::default()
+        }
+    }
+
+    /// Load the history of `namespace` from the data directory.
+    pub fn load(namespace: &str, max_size: usize) -> Result<Self> {
+        let Some(dir) = data_dir() else {
+            return Ok(Self::in_memory(max_size));
+        };
+        Self::load_from(dir.join("history").join(namespace), max_size)
+    }
+
+    pub fn load_from(path: PathBuf, max_size: usize) -> Result<Self> {
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => content.lines().map(str::to_string).collect(),
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("reading {}", path.display()));
+            }
+        };
+        let mut history = Self {
+            path: Some(path),
+            entries,
+            max_size,
+            ..Self::default()
+        };
+        history.truncate();
+        Ok(history)
+    }
+
+    /// Append `query` as newest entry, dropping an older duplicate.
+    pub fn add(&mut self, query: &str) {
+        self.position = None;
+        if query.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != query);
+        self.entries.push(query.to_string());
+        self.truncate();
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).wrap_err_with(|| format!("creating {}", dir.display()))?;
+        }
+        let mut content = self.entries.join("\n");
+        content.push('\n');
+        fs::write(path, content).wrap_err_with(|| format!("writing {}", path.display()))
+    }
+
+    /// Recall the entry before the current one.
+    pub fn recall_older(&mut self, current: &str) -> Option<String> {
+        self.start_recall(current);
+        let position = self.position.unwrap_or(self.entries.len()).checked_sub(1)?;
+        self.position = Some(position);
+        Some(self.entries[position].clone())
+    }
+
+    /// Recall the entry after the current one, or the draft after the newest.
+    pub fn recall_newer(&mut self) -> Option<String> {
+        let position = self.position? + 1;
+        if position < self.entries.len() {
+            self.position = Some(position);
+            Some(self.entries[position].clone())
+        } else {
+            self.position = None;
+            Some(self.draft.clone())
+        }
+    }
+
+    /// Recall the next older entry containing the query typed before recalling.
+    pub fn search_backward(&mut self, current: &str) -> Option<String> {
+        self.start_recall(current);
+        let start = self.position.unwrap_or(self.entries.len());
+        let position = (0..start)
+            .rev()
+            .find(|&i| self.entries[i].contains(&self.draft) && self.entries[i] != current)?;
+        self.position = Some(position);
+        Some(self.entries[position].clone())
+    }
+
+    /// The query was edited, recalling starts again from the newest entry.
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    fn start_recall(&mut self, current: &str) {
+        if self.position.is_none() {
+            self.draft = current.to_string();
+        }
+    }
+
+    fn truncate(&mut self) {
+        let excess = self.entries.len().saturating_sub(self.max_size);
+        self.entries.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finder::source::Source;
+    use std::{env, process};
+
+    fn history(entries: &[&str]) -> History {
+        let mut history = History::in_memory(10);
+        for entry in entries {
+            history.add(entry);
+        }
+        history
+    }
+
+    #[test]
+    fn recall_steps_back_and_returns_to_the_draft() {
+        let mut history = history(&["dune", "hyp", "neuro"]);
+        assert_eq!(history.recall_newer(), None);
+        let older: Vec<_> = (0..3)
+            .map(|_| history.recall_older("sn").unwrap())
+            .collect();
+        assert_eq!(older, ["neuro", "hyp", "dune"]);
+        assert_eq!(history.recall_older("sn"), None);
+        assert_eq!(history.recall_newer().as_deref(), Some("hyp"));
+        assert_eq!(history.recall_newer().as_deref(), Some("neuro"));
+        assert_eq!(history.recall_newer().as_deref(), Some("sn"));
+        assert_eq!(history.recall_newer(), None);
+    }
+
+    #[test]
+    fn reset_recalls_from_the_newest_entry() {
+        let mut history = history(&["dune", "hyp", "neuro"]);
+        history.recall_older("");
+        history.recall_older("");
+        history.reset();
+        assert_eq!(history.recall_older("").as_deref(), Some("neuro"));
+    }
+
+    #[test]
+    fn add_moves_duplicates_to_the_end_and_truncates() {
+        let mut history = History::in_memory(3);
+        for query in ["a", "b", "", "a", "c", "d"] {
+            history.add(query);
+        }
+        assert_eq!(history.entries, ["a", "c", "d"]);
+    }
+
+    #[test]
+    fn search_backward_finds_older_entries_containing_the_draft() {
+        let mut history = history(&["git log", "cargo test", "git status", "ls"]);
+        assert_eq!(
+            history.search_backward("git").as_deref(),
+            Some("git status")
+        );
+        assert_eq!(
+            history.search_backward("git status").as_deref(),
+            Some("git log")
+        );
+        assert_eq!(history.search_backward("git log"), None);
+        history.reset();
+        assert_eq!(history.search_backward("xyz"), None);
+    }
+
+    #[test]
+    fn sources_keep_their_own_history_file() {
+        let dir = env::temp_dir().join(format!("ratatui_examples-history-{}", process::id()));
+        let sources = [
+            Source::Command("git branch".to_string()),
+            Source::File("list.txt".into()),
+        ];
+        let [branches, files] = sources.map(|source| dir.join(source.namespace()));
+        assert_ne!(branches, files);
+        let mut history = History::load_from(branches.clone(), 2).unwrap();
+        for query in ["main", "feat", "fix"] {
+            history.add(query);
+        }
+        history.save().unwrap();
+        assert_eq!(
+            History::load_from(branches, 2).unwrap().entries,
+            ["feat", "fix"]
+        );
+        assert!(History::load_from(files, 2).unwrap().entries.is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,128 @@
+use rayon::prelude::*;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, RwLock,
+};
+
+/// items per chunk, also how many are matched between checks for a newer query
+pub const CHUNK_SIZE: usize = 4096;
+
+/// Candidates shared between the reader thread, the match worker and the UI.
+///
+/// Items are only ever appended. They are kept in fixed size chunks, so a
+/// [`Snapshot`] is a few cheap `Arc` clones and matching never holds the lock
+/// while the reader adds more items.
+#[derive(Debug, Default)]
+pub struct ItemStore {
+    chunks: RwLock<Vec<Arc<Vec<String>>>>,
+    /// the reader thread is still running
+    loading: AtomicBool,
+    /// why reading stopped early, if it did
+    error: Mutex<Option<String>>,
+}
+
+impl ItemStore {
+    /// Empty store waiting for a reader to [`extend`](Self::extend) and [`finish`](Self::finish) it.
+    pub fn loading() -> Self {
+        Self {
+            loading: AtomicBool::new(true),
+            ..Self::default()
+        }
+    }
+
+    pub fn from_items(items: Vec<String>) -> Self {
+        let store = Self::default();
+        store.extend(items);
+        store
+    }
+
+    pub fn extend(&self, items: impl IntoIterator<Item = String>) {
+        let mut chunks = self.chunks.write().unwrap_or_else(|err| err.into_inner());
+        for item in items {
+            match chunks.last_mut() {
+                // copies the partial chunk only if a snapshot still holds it
+                Some(chunk) if chunk.len() < CHUNK_SIZE => Arc::make_mut(chunk).push(item),
+                _ => chunks.push(Arc::new(vec![item])),
+            }
+        }
+    }
+
+    /// All items have been read, `error` tells why reading stopped early.
+    pub fn finish(&self, error: Option<String>) {
+        *self.error.lock().unwrap_or_else(|err| err.into_inner()) = error;
+        self.loading.store(false, Ordering::Release);
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading.load(Ordering::Acquire)
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<String> {
+        self.snapshot().get(index).map(str::to_string)
+    }
+
+    /// The items read so far.
+    pub fn snapshot(&self) -> Snapshot {
+        let chunks = self.chunks.read().unwrap_or_else(|err| err.into_inner());
+        Snapshot {
+            chunks: chunks.clone(),
+        }
+    }
+}
+
+/// Items of an [`ItemStore`] at one point in time, addressed by index.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    chunks: Vec<Arc<Vec<String>>>,
+}
+
+impl Snapshot {
+    pub fn len(&self) -> usize {
+        match self.chunks.last() {
+            Some(last) => (self.chunks.len() - 1) * CHUNK_SIZE + last.len(),
+            None => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.chunks
+            .get(index / CHUNK_SIZE)
+            .and_then(|chunk| chunk.get(index % CHUNK_SIZE))
+            .map(String::as_str)
+    }
+
+    /// Chunks from item `start` on, with the index of their first item.
+    pub fn par_chunks_from(
+        &self,
+        start: usize,
+    ) -> impl ParallelIterator<Item = (usize, &[String])> {
+        self.chunks
+            .par_iter()
+            .enumerate()
+            .skip(start / CHUNK_SIZE)
+            .map(move |(i, chunk)| {
+                let offset = i * CHUNK_SIZE;
+                let skip = start.saturating_sub(offset).min(chunk.len());
+                (offset + skip, &chunk[skip..])
+            })
+    }
+}
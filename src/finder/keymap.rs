@@ -0,0 +1,367 @@
+use crate::finder::preview::shell_quote;
+pub use crate::key::KeyChord;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Report, Result,
+};
+use crossterm::event::KeyEvent;
+use std::{collections::HashMap, fmt, str::FromStr};
+use toml::{Table, Value};
+
+/// What a key does, named like the fzf actions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyAction {
+    Accept,
+    Abort,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    /// mark the selected result and move down
+    ToggleDown,
+    ToggleUp,
+    TogglePreview,
+    PreviewUp,
+    PreviewDown,
+    NextMatcher,
+    ToggleScores,
+    HistoryPrev,
+    HistoryNext,
+    HistorySearch,
+    ClearQuery,
+    BackwardChar,
+    ForwardChar,
+    BeginningOfLine,
+    EndOfLine,
+    BackwardWord,
+    ForwardWord,
+    BackwardDeleteChar,
+    DeleteChar,
+    BackwardKillWord,
+    UnixLineDiscard,
+    KillLine,
+    Yank,
+    Undo,
+    Redo,
+    /// do nothing, removes a default binding
+    Ignore,
+    /// `execute(CMD)`: run CMD on the terminal, see [`expand_placeholders`]
+    Execute(String),
+    /// `reload(CMD)`: replace the candidates by the output of CMD
+    Reload(String),
+}
+
+impl KeyAction {
+    const NAMES: [(&'static str, KeyAction); 34] = [
+        ("accept", KeyAction::Accept),
+        ("abort", KeyAction::Abort),
+        ("up", KeyAction::Up),
+        ("down", KeyAction::Down),
+        ("page-up", KeyAction::PageUp),
+        ("page-down", KeyAction::PageDown),
+        ("first", KeyAction::First),
+        ("last", KeyAction::Last),
+        ("toggle-down", KeyAction::ToggleDown),
+        ("toggle-up", KeyAction::ToggleUp),
+        ("toggle-preview", KeyAction::TogglePreview),
+        ("preview-up", KeyAction::PreviewUp),
+        ("preview-down", KeyAction::PreviewDown),
+        ("next-matcher", KeyAction::NextMatcher),
+        ("toggle-scores", KeyAction::ToggleScores),
+        ("history-prev", KeyAction::HistoryPrev),
+        ("history-next", KeyAction::HistoryNext),
+        ("history-search", KeyAction::HistorySearch),
+        ("clear-query", KeyAction::ClearQuery),
+        ("backward-char", KeyAction::BackwardChar),
+        ("forward-char", KeyAction::ForwardChar),
+        ("beginning-of-line", KeyAction::BeginningOfLine),
+        ("end-of-line", KeyAction::EndOfLine),
+        ("backward-word", KeyAction::BackwardWord),
+        ("forward-word", KeyAction::ForwardWord),
+        ("backward-delete-char", KeyAction::BackwardDeleteChar),
+        ("delete-char", KeyAction::DeleteChar),
+        ("backward-kill-word", KeyAction::BackwardKillWord),
+        ("unix-line-discard", KeyAction::UnixLineDiscard),
+        ("kill-line", KeyAction::KillLine),
+        ("yank", KeyAction::Yank),
+        ("undo", KeyAction::Undo),
+        ("redo", KeyAction::Redo),
+        ("ignore", KeyAction::Ignore),
+    ];
+}
+
+impl fmt::Display for KeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyAction::Execute(command) => return write!(f, "execute({command})"),
+            KeyAction::Reload(command) => return write!(f, "reload({command})"),
+            _ => {}
+        }
+        let name = Self::NAMES
+            .iter()
+            .find(|(_, action)| action == self)
+            .map_or("?", |(name, _)| name);
+        f.write_str(name)
+    }
+}
+
+impl FromStr for KeyAction {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some((name, argument)) = s.strip_suffix(')').and_then(|s| s.split_once('(')) {
+            if argument.trim().is_empty() {
+                bail!("{name}(...) expects a command");
+            }
+            return match name {
+                "execute" => Ok(KeyAction::Execute(argument.to_string())),
+                "reload" => Ok(KeyAction::Reload(argument.to_string())),
+                _ => bail!("unknown action {name}(...), expected execute(...) or reload(...)"),
+            };
+        }
+        match Self::NAMES.iter().find(|(name, _)| *name == s) {
+            Some((_, action)) => Ok(action.clone()),
+            None => bail!("unknown action {s}, see --help for the list of actions"),
+        }
+    }
+}
+
+/// Key chords and the actions they trigger.
+///
+/// Keys without a binding type their char into the query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: HashMap<KeyChord, KeyAction>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        const DEFAULT_BINDINGS: [(&str, KeyAction); 42] = [
+            ("enter", KeyAction::Accept),
+            ("esc", KeyAction::Abort),
+            ("ctrl-c", KeyAction::Abort),
+            ("up", KeyAction::Up),
+            ("ctrl-p", KeyAction::Up),
+            ("down", KeyAction::Down),
+            ("ctrl-n", KeyAction::Down),
+            ("page-up", KeyAction::PageUp),
+            ("page-down", KeyAction::PageDown),
+            ("home", KeyAction::First),
+            ("ctrl-home", KeyAction::First),
+            ("end", KeyAction::Last),
+            ("ctrl-end", KeyAction::Last),
+            ("tab", KeyAction::ToggleDown),
+            ("btab", KeyAction::ToggleUp),
+            ("alt-p", KeyAction::TogglePreview),
+            ("shift-up", KeyAction::PreviewUp),
+            ("shift-down", KeyAction::PreviewDown),
+            ("ctrl-s", KeyAction::NextMatcher),
+            ("f2", KeyAction::ToggleScores),
+            ("alt-up", KeyAction::HistoryPrev),
+            ("alt-down", KeyAction::HistoryNext),
+            ("ctrl-r", KeyAction::HistorySearch),
+            ("left", KeyAction::BackwardChar),
+            ("ctrl-b", KeyAction::BackwardChar),
+            ("right", KeyAction::ForwardChar),
+            ("ctrl-f", KeyAction::ForwardChar),
+            ("ctrl-a", KeyAction::BeginningOfLine),
+            ("ctrl-e", KeyAction::EndOfLine),
+            ("alt-b", KeyAction::BackwardWord),
+            ("alt-f", KeyAction::ForwardWord),
+            ("backspace", KeyAction::BackwardDeleteChar),
+            ("ctrl-h", KeyAction::BackwardDeleteChar),
+            ("delete", KeyAction::DeleteChar),
+            ("ctrl-w", KeyAction::BackwardKillWord),
+            ("alt-bspace", KeyAction::BackwardKillWord),
+            ("ctrl-u", KeyAction::UnixLineDiscard),
+            ("ctrl-k", KeyAction::KillLine),
+            ("ctrl-y", KeyAction::Yank),
+            ("ctrl-z", KeyAction::Undo),
+            ("alt-z", KeyAction::Redo),
+            ("alt-c", KeyAction::ClearQuery),
+        ];
+        let bindings = DEFAULT_BINDINGS
+            .into_iter()
+            .map(|(chord, action)| (chord.parse().expect("valid default key"), action))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn get(&self, key: &KeyEvent) -> Option<&KeyAction> {
+        self.bindings.get(&KeyChord::from(key))
+    }
+
+    pub fn bind(&mut self, chord: KeyChord, action: KeyAction) {
+        self.bindings.insert(chord, action);
+    }
+
+    /// Apply the `[bindings]` table of the config file.
+    pub fn bind_toml(&mut self, bindings: Table) -> Result<()> {
+        for (chord, action) in bindings {
+            let Value::String(action) = action else {
+                bail!("bindings.{chord} expects an action name in quotes");
+            };
+            let key: KeyChord = chord
+                .parse()
+                .wrap_err_with(|| format!("invalid key in bindings.{chord}"))?;
+            let action = action
+                .parse()
+                .wrap_err_with(|| format!("invalid action in bindings.{chord}"))?;
+            self.bind(key, action);
+        }
+        Ok(())
+    }
+}
+
+/// Parse `--bind` bindings like `ctrl-j:down,ctrl-o:execute(vim {})`.
+///
+/// Commas and colons inside the parentheses of a command are part of it.
+pub fn parse_bindings(bindings: &str) -> Result<Vec<(KeyChord, KeyAction)>> {
+    split_bindings(bindings)
+        .into_iter()
+        .filter(|binding| !binding.is_empty())
+        .map(|binding| {
+            // the first colon after a valid key, so `:` itself can be bound
+            let split = binding
+                .match_indices(':')
+                .map(|(i, _)| (&binding[..i], &binding[i + 1..]))
+                .find(|(chord, _)| chord.parse::<KeyChord>().is_ok());
+            let Some((chord, action)) = split else {
+                bail!("invalid binding {binding}, expected KEY:ACTION");
+            };
+            let action = action
+                .parse()
+                .wrap_err_with(|| format!("invalid binding {binding}"))?;
+            Ok((chord.parse()?, action))
+        })
+        .collect()
+}
+
+/// Split at the commas outside of parentheses.
+fn split_bindings(bindings: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in bindings.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 && i > start => {
+                parts.push(&bindings[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&bindings[start..]);
+    parts
+}
+
+/// Fill in the placeholders of an `execute` or `reload` command: `{}` is the
+/// selected item, `{+}` the marked items or the selected one and `{q}` the
+/// query, all shell quoted.
+pub fn expand_placeholders(command: &str, item: &str, items: &[String], query: &str) -> String {
+    let mut expanded = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let (replacement, len) = if rest.starts_with("{}") {
+            (shell_quote(item), 2)
+        } else if rest.starts_with("{+}") {
+            let quoted: Vec<String> = items.iter().map(|item| shell_quote(item)).collect();
+            (quoted.join(" "), 3)
+        } else if rest.starts_with("{q}") {
+            (shell_quote(query), 3)
+        } else {
+            ("{".to_string(), 1)
+        };
+        expanded.push_str(&replacement);
+        rest = &rest[len..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn shift_letters_bind_the_uppercase_key() {
+        let mut keymap = Keymap::default();
+        keymap.bind("shift-a".parse().unwrap(), KeyAction::Accept);
+        let a = key(KeyCode::Char('a'), KeyModifiers::NONE);
+        let shift_a = key(KeyCode::Char('A'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.get(&a), None);
+        assert_eq!(keymap.get(&shift_a), Some(&KeyAction::Accept));
+    }
+
+    #[test]
+    fn home_and_end_move_the_selection() {
+        let keymap = Keymap::default();
+        let get = |code| keymap.get(&key(code, KeyModifiers::NONE));
+        assert_eq!(get(KeyCode::Home), Some(&KeyAction::First));
+        assert_eq!(get(KeyCode::End), Some(&KeyAction::Last));
+        let ctrl_a = key(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.get(&ctrl_a), Some(&KeyAction::BeginningOfLine));
+    }
+
+    #[test]
+    fn shifted_chars_match_without_shift() {
+        let keymap = Keymap::default();
+        let backtab = key(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(keymap.get(&backtab), Some(&KeyAction::ToggleUp));
+        let mut keymap = Keymap::default();
+        keymap.bind("alt-A".parse().unwrap(), KeyAction::Accept);
+        let alt_shift_a = key(KeyCode::Char('A'), KeyModifiers::ALT | KeyModifiers::SHIFT);
+        assert_eq!(keymap.get(&alt_shift_a), Some(&KeyAction::Accept));
+    }
+
+    #[test]
+    fn parse_bind_option() {
+        let bindings = parse_bindings("ctrl-j:down,::accept,ctrl-o:execute(echo a,b:{})").unwrap();
+        assert_eq!(
+            bindings,
+            [
+                ("ctrl-j".parse().unwrap(), KeyAction::Down),
+                (":".parse().unwrap(), KeyAction::Accept),
+                (
+                    "ctrl-o".parse().unwrap(),
+                    KeyAction::Execute("echo a,b:{}".to_string())
+                ),
+            ]
+        );
+        assert!(parse_bindings("ctrl-j").is_err());
+        assert!(parse_bindings("ctrl-j:jump").is_err());
+        assert!(parse_bindings("ctrl-j:execute()").is_err());
+    }
+
+    #[test]
+    fn action_names_round_trip() {
+        for (name, action) in KeyAction::NAMES {
+            assert_eq!(action.to_string(), name);
+            assert_eq!(name.parse::<KeyAction>().unwrap(), action);
+        }
+        let reload: KeyAction = "reload(ls {q})".parse().unwrap();
+        assert_eq!(reload.to_string(), "reload(ls {q})");
+    }
+
+    #[test]
+    fn expand_placeholders_quotes() {
+        let items = ["a b".to_string(), "it's".to_string()];
+        assert_eq!(
+            expand_placeholders("vim {} {+} {q} {x}", "a b", &items, "q"),
+            r"vim 'a b' 'a b' 'it'\''s' 'q' {x}"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Single line text input with readline style editing.
+///
+/// The cursor is a grapheme index, so combining characters and emoji
+/// sequences are edited as one unit, and `cursor_column` accounts for wide
+/// (CJK, emoji) graphemes taking two terminal columns.
+#[derive(Debug, Default, Clone)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize,
+    /// text removed by the last kill (Ctrl-W, Ctrl-U, Ctrl-K), inserted with Ctrl-Y
+    yank_buffer: String,
+    undo_stack: Vec<(String, usize)>,
+    redo_stack: Vec<(String, usize)>,
+    /// consecutive typed chars are undone together
+    last_edit_was_insert: bool,
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// cursor position in graphemes
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace the text as one undoable edit, the cursor moves to its end.
+    pub fn set_text(&mut self, text: &str) {
+        if self.text == text {
+            return;
+        }
+        self.save_undo(false);
+        self.text = text.to_string();
+        self.cursor = self.grapheme_count();
+    }
+
+    /// Terminal columns left of the cursor.
+    pub fn cursor_column(&self) -> usize {
+        self.text[..self.byte_index()].width()
+    }
+
+    /// Place the cursor at the grapheme covering `column`.
+    pub fn set_cursor_column(&mut self, column: usize) {
+        let mut width = 0;
+        let mut cursor = 0;
+        for grapheme in self.text.graphemes(true) {
+            let grapheme_width = grapheme.width();
+            if width + grapheme_width > column {
+                break;
+            }
+            width += grapheme_width;
+            cursor += 1;
+        }
+        self.move_to(cursor);
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        let cursor_moved_left = self.cursor.saturating_sub(1);
+        self.move_to(self.clamp_cursor(cursor_moved_left));
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        let cursor_moved_right = self.cursor.saturating_add(1);
+        self.move_to(self.clamp_cursor(cursor_moved_right));
+    }
+
+    pub fn move_cursor_start(&mut self) {
+        self.move_to(0);
+    }
+
+    pub fn move_cursor_end(&mut self) {
+        self.move_to(self.grapheme_count());
+    }
+
+    /// Alt-B: to the start of the current or previous word.
+    pub fn move_word_left(&mut self) {
+        self.move_to(self.word_start_before(self.cursor, is_alphanumeric));
+    }
+
+    /// Alt-F: to the end of the current or next word.
+    pub fn move_word_right(&mut self) {
+        let graphemes: Vec<&str> = self.text.graphemes(true).collect();
+        let mut cursor = self.cursor;
+        while cursor < graphemes.len() && !is_alphanumeric(graphemes[cursor]) {
+            cursor += 1;
+        }
+        while cursor < graphemes.len() && is_alphanumeric(graphemes[cursor]) {
+            cursor += 1;
+        }
+        self.move_to(cursor);
+    }
+
+    pub fn enter_char(&mut self, new_char: char) {
+        self.save_undo(true);
+        let index = self.byte_index();
+        self.text.insert(index, new_char);
+        // a combining char merges with the grapheme before it
+        let graphemes_before = self.text[..index + new_char.len_utf8()]
+            .graphemes(true)
+            .count();
+        self.cursor = self.clamp_cursor(graphemes_before);
+    }
+
+    /// Backspace: delete the grapheme left of the cursor.
+    pub fn delete_char(&mut self) {
+        if self.cursor != 0 {
+            self.delete_range(self.cursor - 1, self.cursor);
+        }
+    }
+
+    /// Delete: delete the grapheme under the cursor.
+    pub fn delete_char_forward(&mut self) {
+        if self.cursor < self.grapheme_count() {
+            self.delete_range(self.cursor, self.cursor + 1);
+        }
+    }
+
+    /// Ctrl-W: kill the whitespace separated word before the cursor.
+    pub fn delete_word_before(&mut self) {
+        let start = self.word_start_before(self.cursor, |g| !is_whitespace(g));
+        self.kill_range(start, self.cursor);
+    }
+
+    /// Ctrl-U: kill everything before the cursor.
+    pub fn kill_to_start(&mut self) {
+        self.kill_range(0, self.cursor);
+    }
+
+    /// Ctrl-K: kill everything after the cursor.
+    pub fn kill_to_end(&mut self) {
+        self.kill_range(self.cursor, self.grapheme_count());
+    }
+
+    /// Ctrl-Y: insert the last killed text.
+    pub fn yank(&mut self) {
+        if self.yank_buffer.is_empty() {
+            return;
+        }
+        self.save_undo(false);
+        let index = self.byte_index();
+        self.text.insert_str(index, &self.yank_buffer);
+        self.cursor += self.yank_buffer.graphemes(true).count();
+    }
+
+    pub fn undo(&mut self) {
+        if let Some((text, cursor)) = self.undo_stack.pop() {
+            let current = (std::mem::replace(&mut self.text, text), self.cursor);
+            self.redo_stack.push(current);
+            self.cursor = cursor;
+            self.last_edit_was_insert = false;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some((text, cursor)) = self.redo_stack.pop() {
+            let current = (std::mem::replace(&mut self.text, text), self.cursor);
+            self.undo_stack.push(current);
+            self.cursor = cursor;
+            self.last_edit_was_insert = false;
+        }
+    }
+
+    /// Byte offset of the cursor in the text.
+    pub fn byte_index(&self) -> usize {
+        self.text
+            .grapheme_indices(true)
+            .map(|(i, _)| i)
+            .nth(self.cursor)
+            .unwrap_or(self.text.len())
+    }
+
+    pub fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.grapheme_count())
+    }
+
+    /// Move the cursor, typing afterwards starts a new undo step.
+    fn move_to(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        self.last_edit_was_insert = false;
+    }
+
+    fn grapheme_count(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+
+    /// Byte range of the graphemes `start..end`.
+    fn byte_range(&self, start: usize, end: usize) -> std::ops::Range<usize> {
+        let offset = |n| {
+            self.text
+                .grapheme_indices(true)
+                .map(|(i, _)| i)
+                .nth(n)
+                .unwrap_or(self.text.len())
+        };
+        offset(start)..offset(end)
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) -> String {
+        self.save_undo(false);
+        let range = self.byte_range(start, end);
+        let removed = self.text.drain(range).collect();
+        self.cursor = start;
+        removed
+    }
+
+    fn kill_range(&mut self, start: usize, end: usize) {
+        if start < end {
+            self.yank_buffer = self.delete_range(start, end);
+        }
+    }
+
+    /// Start of the word ending at or before `cursor`, skipping trailing non-word graphemes.
+    fn word_start_before(&self, cursor: usize, is_word: impl Fn(&str) -> bool) -> usize {
+        let graphemes: Vec<&str> = self.text.graphemes(true).take(cursor).collect();
+        let mut start = graphemes.len();
+        while start > 0 && !is_word(graphemes[start - 1]) {
+            start -= 1;
+        }
+        while start > 0 && is_word(graphemes[start - 1]) {
+            start -= 1;
+        }
+        start
+    }
+
+    fn save_undo(&mut self, insert: bool) {
+        if !(insert && self.last_edit_was_insert) {
+            self.undo_stack.push((self.text.clone(), self.cursor));
+        }
+        self.redo_stack.clear();
+        self.last_edit_was_insert = insert;
+    }
+}
+
+fn is_alphanumeric(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(char::is_alphanumeric)
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        text.chars().for_each(|c| editor.enter_char(c));
+        editor
+    }
+
+    #[test]
+    fn enter_char_at_cursor() {
+        let mut editor = editor("ac");
+        editor.move_cursor_left();
+        editor.enter_char('b');
+        assert_eq!(editor.text(), "abc");
+        assert_eq!(editor.cursor(), 2);
+    }
+
+    #[test]
+    fn enter_multi_byte_chars() {
+        let mut editor = editor("äö");
+        assert_eq!(editor.cursor(), 2);
+        assert_eq!(editor.byte_index(), 4);
+        editor.move_cursor_left();
+        editor.enter_char('€');
+        assert_eq!(editor.text(), "ä€ö");
+        assert_eq!(editor.byte_index(), "ä€".len());
+    }
+
+    #[test]
+    fn combining_char_joins_previous_grapheme() {
+        let editor = editor("e\u{301}x");
+        assert_eq!(editor.cursor(), 2);
+        assert_eq!(editor.byte_index(), editor.text().len());
+    }
+
+    #[test]
+    fn delete_char_removes_whole_grapheme() {
+        let mut editor = editor("ae\u{301}");
+        editor.delete_char();
+        assert_eq!(editor.text(), "a");
+        assert_eq!(editor.cursor(), 1);
+        editor.move_cursor_start();
+        editor.delete_char();
+        assert_eq!(editor.text(), "a");
+    }
+
+    #[test]
+    fn delete_char_forward_multi_byte() {
+        let mut editor = editor("日本語");
+        editor.move_cursor_start();
+        editor.delete_char_forward();
+        assert_eq!(editor.text(), "本語");
+        assert_eq!(editor.byte_index(), 0);
+    }
+
+    #[test]
+    fn byte_index_past_end_is_text_length() {
+        let editor = editor("🦀x");
+        assert_eq!(editor.byte_index(), "🦀x".len());
+    }
+
+    #[test]
+    fn clamp_cursor_to_grapheme_count() {
+        let editor = editor("ñö");
+        assert_eq!(editor.clamp_cursor(0), 0);
+        assert_eq!(editor.clamp_cursor(2), 2);
+        assert_eq!(editor.clamp_cursor(5), 2);
+    }
+
+    #[test]
+    fn cursor_column_counts_wide_graphemes() {
+        let mut editor = editor("a日b");
+        assert_eq!(editor.cursor_column(), 4);
+        editor.set_cursor_column(2);
+        assert_eq!(editor.cursor(), 1);
+        editor.set_cursor_column(3);
+        assert_eq!(editor.cursor(), 2);
+    }
+
+    #[test]
+    fn kill_and_yank() {
+        let mut editor = editor("foo bar");
+        editor.delete_word_before();
+        assert_eq!(editor.text(), "foo ");
+        editor.move_cursor_start();
+        editor.yank();
+        assert_eq!(editor.text(), "barfoo ");
+        assert_eq!(editor.cursor(), 3);
+    }
+
+    #[test]
+    fn undo_typed_word_at_once() {
+        let mut editor = editor("foo");
+        editor.move_cursor_start();
+        editor.kill_to_end();
+        assert_eq!(editor.text(), "");
+        editor.undo();
+        assert_eq!(editor.text(), "foo");
+        editor.undo();
+        assert_eq!(editor.text(), "");
+        editor.redo();
+        assert_eq!(editor.text(), "foo");
+    }
+}
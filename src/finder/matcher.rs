@@ -0,0 +1,244 @@
+use color_eyre::{eyre::bail, Report, Result};
+use fuzzy_matcher::{clangd::ClangdMatcher, skim::SkimMatcherV2, FuzzyMatcher};
+use regex::{Regex, RegexBuilder};
+use std::{fmt, str::FromStr, sync::Arc, sync::RwLock};
+
+/// Matching algorithm, selected with `--algo` or cycled at runtime.
+///
+/// All of them implement [`FuzzyMatcher`], so every algorithm yields
+/// `(score, char indices)` for an item and the results look the same.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatcherKind {
+    #[default]
+    Skim,
+    Clangd,
+    Exact,
+    Prefix,
+    Regex,
+}
+
+impl MatcherKind {
+    pub const ALL: [MatcherKind; 5] = [
+        MatcherKind::Skim,
+        MatcherKind::Clangd,
+        MatcherKind::Exact,
+        MatcherKind::Prefix,
+        MatcherKind::Regex,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MatcherKind::Skim => "skim",
+            MatcherKind::Clangd => "clangd",
+            MatcherKind::Exact => "exact",
+            MatcherKind::Prefix => "prefix",
+            MatcherKind::Regex => "regex",
+        }
+    }
+
+    /// The following algorithm, wrapping around.
+    pub fn next(self) -> Self {
+        let position = Self::ALL.iter().position(|kind| *kind == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+
+    pub fn build(self, case: CaseMode) -> Arc<dyn FuzzyMatcher> {
+        match self {
+            MatcherKind::Skim => {
+                let matcher = SkimMatcherV2::default();
+                Arc::new(match case {
+                    CaseMode::Smart => matcher.smart_case(),
+                    CaseMode::Ignore => matcher.ignore_case(),
+                    CaseMode::Respect => matcher.respect_case(),
+                })
+            }
+            MatcherKind::Clangd => {
+                let matcher = ClangdMatcher::default();
+                Arc::new(match case {
+                    CaseMode::Smart => matcher.smart_case(),
+                    CaseMode::Ignore => matcher.ignore_case(),
+                    CaseMode::Respect => matcher.respect_case(),
+                })
+            }
+            MatcherKind::Exact => Arc::new(ExactMatcher { case }),
+            MatcherKind::Prefix => Arc::new(PrefixMatcher { case }),
+            MatcherKind::Regex => Arc::new(RegexMatcher {
+                case,
+                cache: RwLock::default(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for MatcherKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MatcherKind {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Self::ALL.into_iter().find(|kind| kind.name() == s) {
+            Some(kind) => Ok(kind),
+            None => {
+                bail!("unknown matcher {s}, expected one of skim, clangd, exact, prefix, regex")
+            }
+        }
+    }
+}
+
+/// Whether uppercase and lowercase letters match each other, `--case`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    /// case sensitive only with uppercase letters in the pattern
+    #[default]
+    Smart,
+    Ignore,
+    Respect,
+}
+
+impl CaseMode {
+    pub fn is_case_sensitive(self, pattern: &str) -> bool {
+        match self {
+            CaseMode::Smart => pattern.chars().any(char::is_uppercase),
+            CaseMode::Ignore => false,
+            CaseMode::Respect => true,
+        }
+    }
+}
+
+impl FromStr for CaseMode {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "smart" => Ok(CaseMode::Smart),
+            "ignore" => Ok(CaseMode::Ignore),
+            "respect" => Ok(CaseMode::Respect),
+            _ => bail!("unknown case mode {s}, expected smart, ignore or respect"),
+        }
+    }
+}
+
+/// Earlier matches in shorter items score higher.
+fn position_score(start: usize, choice_len: usize) -> i64 {
+    1000 - (2 * start + choice_len) as i64
+}
+
+fn chars_equal(a: char, b: char, case_sensitive: bool) -> bool {
+    a == b || !case_sensitive && a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Where a substring has to be found in an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Anywhere,
+    Start,
+    End,
+    /// the whole item
+    Line,
+}
+
+/// Char position of `pattern` in `choice`.
+fn find_chars(
+    choice: &[char],
+    pattern: &[char],
+    anchor: Anchor,
+    case_sensitive: bool,
+) -> Option<usize> {
+    let last_start = choice.len().checked_sub(pattern.len())?;
+    let starts = match anchor {
+        Anchor::Anywhere => 0..=last_start,
+        Anchor::Start => 0..=0,
+        Anchor::End => last_start..=last_start,
+        Anchor::Line if last_start == 0 => 0..=0,
+        Anchor::Line => return None,
+    };
+    starts.into_iter().find(|&start| {
+        pattern
+            .iter()
+            .zip(&choice[start..])
+            .all(|(p, c)| chars_equal(*p, *c, case_sensitive))
+    })
+}
+
+/// Find `pattern` as a contiguous substring, yielding the same
+/// `(score, char indices)` as the fuzzy matchers.
+pub fn substring_indices(
+    choice: &str,
+    pattern: &str,
+    anchor: Anchor,
+    case: CaseMode,
+) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = case.is_case_sensitive(pattern);
+    let choice: Vec<char> = choice.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let start = find_chars(&choice, &pattern, anchor, case_sensitive)?;
+    let indices = (start..start + pattern.len()).collect();
+    Some((position_score(start, choice.len()), indices))
+}
+
+/// Matches the pattern as a contiguous substring.
+#[derive(Default)]
+pub struct ExactMatcher {
+    pub case: CaseMode,
+}
+
+impl FuzzyMatcher for ExactMatcher {
+    fn fuzzy_indices(&self, choice: &str, pattern: &str) -> Option<(i64, Vec<usize>)> {
+        substring_indices(choice, pattern, Anchor::Anywhere, self.case)
+    }
+}
+
+/// Matches items starting with the pattern.
+#[derive(Default)]
+pub struct PrefixMatcher {
+    pub case: CaseMode,
+}
+
+impl FuzzyMatcher for PrefixMatcher {
+    fn fuzzy_indices(&self, choice: &str, pattern: &str) -> Option<(i64, Vec<usize>)> {
+        substring_indices(choice, pattern, Anchor::Start, self.case)
+    }
+}
+
+/// Matches the pattern as a regular expression, invalid patterns match nothing.
+#[derive(Default)]
+pub struct RegexMatcher {
+    pub case: CaseMode,
+    /// last compiled pattern, the same pattern is matched against every item
+    cache: RwLock<Option<(String, Option<Regex>)>>,
+}
+
+impl RegexMatcher {
+    fn regex(&self, pattern: &str) -> Option<Regex> {
+        if let Ok(cache) = self.cache.read() {
+            if let Some((cached_pattern, regex)) = cache.as_ref() {
+                if cached_pattern == pattern {
+                    return regex.clone();
+                }
+            }
+        }
+        let case_sensitive = self.case.is_case_sensitive(pattern);
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .ok();
+        if let Ok(mut cache) = self.cache.write() {
+            *cache = Some((pattern.to_string(), regex.clone()));
+        }
+        regex
+    }
+}
+
+impl FuzzyMatcher for RegexMatcher {
+    fn fuzzy_indices(&self, choice: &str, pattern: &str) -> Option<(i64, Vec<usize>)> {
+        let found = self.regex(pattern)?.find(choice)?;
+        let start = choice[..found.start()].chars().count();
+        let len = found.as_str().chars().count();
+        let indices = (start..start + len).collect();
+        Some((position_score(start, choice.chars().count()), indices))
+    }
+}
@@ -0,0 +1,129 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Text folded for accent insensitive matching, see [`fold`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Folded {
+    pub text: String,
+    /// char index in the original text of every char of `text`
+    origins: Vec<usize>,
+}
+
+impl Folded {
+    /// Map sorted char indices into the folded text back onto the original.
+    pub fn original_indices(&self, indices: Vec<usize>) -> Vec<usize> {
+        let mut original: Vec<usize> = indices
+            .into_iter()
+            .filter_map(|i| self.origins.get(i).copied())
+            .collect();
+        original.dedup();
+        original
+    }
+}
+
+/// Fold accented letters to their base letters, so `café` matches `cafe`.
+///
+/// German umlauts and `ß` are spelled out as in `Goedel` for `Gödel`, other
+/// letters lose their combining marks. Returns `None` if nothing changes,
+/// which is always the case for ASCII text.
+pub fn fold(text: &str) -> Option<Folded> {
+    fold_with(text, true)
+}
+
+/// Like [`fold`], but umlauts lose their dots too, `Godel` for `Gödel`.
+pub fn fold_to_base(text: &str) -> Option<Folded> {
+    fold_with(text, false)
+}
+
+fn fold_with(text: &str, spell_out: bool) -> Option<Folded> {
+    if text.is_ascii() {
+        return None;
+    }
+    let mut folded = Folded {
+        text: String::with_capacity(text.len()),
+        origins: Vec::with_capacity(text.len()),
+    };
+    let mut changed = false;
+    for (i, c) in text.chars().enumerate() {
+        let mut push = |c: char| {
+            folded.text.push(c);
+            folded.origins.push(i);
+        };
+        match spelled_out(c).filter(|_| spell_out) {
+            Some(replacement) => {
+                replacement.chars().for_each(&mut push);
+                changed = true;
+            }
+            None if c.is_ascii() => push(c),
+            None => {
+                for decomposed in std::iter::once(c).nfd() {
+                    if is_combining_mark(decomposed) {
+                        changed = true;
+                    } else {
+                        push(decomposed);
+                    }
+                }
+            }
+        }
+    }
+    changed.then_some(folded)
+}
+
+/// Fold a query term to base letters, see [`fold_to_base`], and if it has
+/// umlauts or `ß` spelled out too, see [`fold`].
+///
+/// Matching both forms against both folds of an item, `Gödel` finds
+/// `Gödel`, `Godel` and `Goedel`.
+pub fn fold_pattern(pattern: &str) -> (String, Option<String>) {
+    let base = fold_to_base(pattern).map_or_else(|| pattern.to_string(), |folded| folded.text);
+    let spelled = fold(pattern)
+        .map(|folded| folded.text)
+        .filter(|spelled| *spelled != base);
+    (base, spelled)
+}
+
+fn spelled_out(c: char) -> Option<&'static str> {
+    let replacement = match c {
+        'ä' => "ae",
+        'ö' => "oe",
+        'ü' => "ue",
+        'Ä' => "Ae",
+        'Ö' => "Oe",
+        'Ü' => "Ue",
+        'ß' => "ss",
+        _ => return None,
+    };
+    Some(replacement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn umlauts_fold_to_both_forms() {
+        assert_eq!(fold("Gödel").unwrap().text, "Goedel");
+        assert_eq!(fold_to_base("Gödel").unwrap().text, "Godel");
+        // `ß` has no base letter
+        assert_eq!(fold("Straße").unwrap().text, "Strasse");
+        assert!(fold_to_base("Straße").is_none());
+    }
+
+    #[test]
+    fn patterns_fold_to_base_and_spelled_out() {
+        let pattern =
+            |base: &str, spelled: Option<&str>| (base.to_string(), spelled.map(str::to_string));
+        assert_eq!(fold_pattern("Gödel"), pattern("Godel", Some("Goedel")));
+        assert_eq!(fold_pattern("café"), pattern("cafe", None));
+        assert_eq!(fold_pattern("Straße"), pattern("Straße", Some("Strasse")));
+        assert_eq!(fold_pattern("plain"), pattern("plain", None));
+    }
+
+    #[test]
+    fn indices_map_back_to_the_original_chars() {
+        let folded = fold("ßa").unwrap();
+        assert_eq!(folded.text, "ssa");
+        assert_eq!(folded.original_indices(vec![0, 1, 2]), [0, 1]);
+        assert!(fold("plain").is_none());
+        assert!(fold("日本").is_none());
+    }
+}
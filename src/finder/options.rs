@@ -0,0 +1,225 @@
+use crate::finder::{
+    fields::{FieldRange, Fields},
+    history::DEFAULT_HISTORY_SIZE,
+    keymap::{parse_bindings, KeyAction, KeyChord},
+    matcher::{CaseMode, MatcherKind},
+    preview::PreviewPosition,
+    source::Source,
+    theme::{parse_colors, ColorSetting},
+};
+use crate::tui::Height;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+usage: fuzzy_finder [OPTIONS] [FILE]
+
+Pick lines from FILE, piped stdin or the output of a command.
+Without any of them `$FUZZY_FINDER_DEFAULT_COMMAND` or `find . -type f` is run.
+
+options:
+  -c, --command CMD   read candidates from the output of CMD
+  -m, --multi         mark several entries with Tab/Shift-Tab
+      --height N|N%   draw below the prompt with this height instead of fullscreen
+      --no-mouse      leave mouse clicks and scrolling to the terminal
+      --algo NAME     matching algorithm: skim (default), clangd, exact, prefix, regex
+      --case smart|ignore|respect
+                      case sensitivity, smart (default) respects case only
+                      if the query contains uppercase letters
+      --normalize     match accented letters by their base letters, Godel and
+                      Goedel find Gödel
+      --preview CMD   show the output of CMD for the selected entry, {} is replaced by it
+      --preview-window right|bottom
+                      show the preview pane there, without --preview files are shown
+      --history-size N
+                      number of queries remembered per source (default 1000)
+      --no-history    neither load nor save the query history
+      --frecency      rank entries accepted often and recently higher
+      --scores        show score, matched positions and matcher per entry, toggled with F2
+  -d, --delimiter RE  split entries into fields at RE instead of whitespace
+      --nth FIELDS    match only these fields, like 2 or 1,3.. or -1
+      --with-nth FIELDS
+                      show only these fields
+      --accept-nth FIELDS
+                      print only these fields of the accepted entries
+      --bind KEY:ACTION[,KEY:ACTION...]
+                      bind keys to actions, like ctrl-j:down,ctrl-k:up
+      --color SPEC    color scheme and colors, like light,hl:red:bold
+      --config FILE   read key bindings and colors from FILE instead of
+                      $XDG_CONFIG_HOME/ratatui_examples/fuzzy_finder.toml
+      --script FILE   run the steps in FILE without a terminal and print the
+                      screen after each step, for testing
+  -h, --help          print this help
+
+search syntax (all space separated terms have to match):
+  term    fuzzy match       'term   exact match
+  ^term   prefix            term$   suffix
+  !term   must not contain  a | b   a or b
+
+fields start at 1, negative ones count from the end, N..M is a range
+
+key bindings, in the config file as `ctrl-j = \"down\"` lines below `[bindings]`:
+  keys     enter esc tab btab bspace delete up down left right home end
+           page-up page-down space f1..f12 or a char, with ctrl- alt- shift-
+  actions  accept abort up down page-up page-down first last toggle-down
+           toggle-up toggle-preview preview-up preview-down next-matcher
+           toggle-scores history-prev history-next history-search clear-query
+           backward-char forward-char beginning-of-line end-of-line
+           backward-word forward-word backward-delete-char delete-char
+           backward-kill-word unix-line-discard kill-line yank undo redo
+           ignore (unbinds the key, unbound chars are typed into the query)
+           execute(CMD) runs CMD on the terminal, reload(CMD) replaces the
+           entries by its output; {} in CMD is the selected entry, {+} the
+           marked ones and {q} the query, e.g. --bind 'ctrl-o:execute(vim {})'
+
+colors, in the config file as `hl = \"red:bold\"` lines below `[colors]`:
+  schemes   dark (default, bw if $NO_COLOR is set), light, high-contrast, bw
+  elements  fg bg hl (matches) fg+ bg+ (selected entry) border label
+            prompt marker info preview-fg preview-bg
+  values    a color like red, bright-blue, 208 or #ff8700, and/or bold dim
+            italic underline reverse, e.g. fg+:black:bold,bg+:#ffd700
+
+exit status:
+  0    an entry was selected and printed to stdout
+  1    nothing was selected (no match)
+  130  aborted with Esc or Ctrl-C";
+
+/// Command line options of the fuzzy finder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub source: Source,
+    pub multi: bool,
+    /// inline viewport instead of the alternate screen
+    pub height: Option<Height>,
+    pub mouse: bool,
+    pub matcher: MatcherKind,
+    pub case: CaseMode,
+    pub normalize: bool,
+    pub preview_command: Option<String>,
+    /// set if the preview pane is shown on start
+    pub preview_window: Option<PreviewPosition>,
+    pub history_size: usize,
+    pub history: bool,
+    pub frecency: bool,
+    pub fields: Fields,
+    pub scores: bool,
+    /// `--config`, replaces the default config file
+    pub config: Option<PathBuf>,
+    /// `--bind`, applied over the config file
+    pub bindings: Vec<(KeyChord, KeyAction)>,
+    /// `--color`, applied over the config file
+    pub colors: Vec<ColorSetting>,
+    /// `--script`, run headless instead of on the terminal
+    pub script: Option<PathBuf>,
+    pub help: bool,
+}
+
+impl Options {
+    /// Parse the arguments without the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut source = None;
+        let mut multi = false;
+        let mut height = None;
+        let mut mouse = true;
+        let mut matcher = MatcherKind::default();
+        let mut case = CaseMode::default();
+        let mut normalize = false;
+        let mut preview_command = None;
+        let mut preview_window = None;
+        let mut history_size = DEFAULT_HISTORY_SIZE;
+        let mut history = true;
+        let mut frecency = false;
+        let mut fields = Fields::default();
+        let mut scores = false;
+        let mut config = None;
+        let mut bindings = Vec::new();
+        let mut colors = Vec::new();
+        let mut script = None;
+        let mut help = false;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => help = true,
+                "-m" | "--multi" => multi = true,
+                "--height" => height = Some(next_value(&arg, &mut args)?.parse()?),
+                "--no-mouse" => mouse = false,
+                "--algo" => matcher = next_value(&arg, &mut args)?.parse()?,
+                "--case" => case = next_value(&arg, &mut args)?.parse()?,
+                "--normalize" => normalize = true,
+                "--preview" => {
+                    preview_command = Some(next_value(&arg, &mut args)?);
+                    preview_window.get_or_insert_with(PreviewPosition::default);
+                }
+                "--preview-window" => {
+                    preview_window = Some(next_value(&arg, &mut args)?.parse()?);
+                }
+                "--history-size" => {
+                    let value = next_value(&arg, &mut args)?;
+                    history_size = value
+                        .parse()
+                        .wrap_err_with(|| format!("{arg} expects a number, got {value}"))?;
+                }
+                "--no-history" => history = false,
+                "--frecency" => frecency = true,
+                "--scores" => scores = true,
+                "-d" | "--delimiter" => fields.delimiter = next_value(&arg, &mut args)?.parse()?,
+                "--nth" => fields.nth = FieldRange::parse_list(&next_value(&arg, &mut args)?)?,
+                "--with-nth" => {
+                    fields.with_nth = FieldRange::parse_list(&next_value(&arg, &mut args)?)?;
+                }
+                "--accept-nth" => {
+                    fields.accept_nth = FieldRange::parse_list(&next_value(&arg, &mut args)?)?;
+                }
+                "--bind" => bindings.extend(parse_bindings(&next_value(&arg, &mut args)?)?),
+                "--color" => colors.extend(parse_colors(&next_value(&arg, &mut args)?)?),
+                "--config" => config = Some(PathBuf::from(next_value(&arg, &mut args)?)),
+                "--script" => script = Some(PathBuf::from(next_value(&arg, &mut args)?)),
+                "-c" | "--command" => {
+                    let command = next_value(&arg, &mut args)?;
+                    source = set_source(source, Source::Command(command))?;
+                }
+                "-" => source = set_source(source, Source::Stdin)?,
+                _ if arg.starts_with('-') => bail!("unknown option {arg}, see --help"),
+                _ => source = set_source(source, Source::File(PathBuf::from(arg)))?,
+            }
+        }
+        Ok(Self {
+            source: source.unwrap_or_else(Source::detect),
+            multi,
+            height,
+            mouse,
+            matcher,
+            case,
+            normalize,
+            preview_command,
+            preview_window,
+            history_size,
+            history,
+            frecency,
+            fields,
+            scores,
+            config,
+            bindings,
+            colors,
+            script,
+            help,
+        })
+    }
+}
+
+fn next_value(arg: &str, args: &mut impl Iterator<Item = String>) -> Result<String> {
+    match args.next() {
+        Some(value) => Ok(value),
+        None => bail!("{arg} expects a value"),
+    }
+}
+
+fn set_source(current: Option<Source>, new: Source) -> Result<Option<Source>> {
+    if let Some(current) = current {
+        bail!("only one source allowed, got {current:?} and {new:?}");
+    }
+    Ok(Some(new))
+}
@@ -0,0 +1,173 @@
+use color_eyre::{eyre::bail, Report, Result};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+    str::FromStr,
+    sync::Arc,
+    thread,
+};
+
+/// previews kept around for moving back and forth in the result list
+const PREVIEW_CACHE_SIZE: usize = 100;
+/// more than fits any terminal, keeps huge files and outputs cheap
+const MAX_PREVIEW_LINES: usize = 1000;
+const MAX_PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// Where the preview pane is placed next to the results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PreviewPosition {
+    #[default]
+    Right,
+    Bottom,
+}
+
+impl FromStr for PreviewPosition {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "right" => Ok(PreviewPosition::Right),
+            "bottom" => Ok(PreviewPosition::Bottom),
+            _ => bail!("unknown preview position {s}, expected right or bottom"),
+        }
+    }
+}
+
+/// Lines shown in the preview pane for `item`.
+///
+/// With a command, `{}` in it is replaced by the quoted item and its output
+/// is shown. Otherwise files are shown by content and directories by entries.
+pub fn generate_preview(command: Option<&str>, item: &str) -> Vec<String> {
+    let text = match command {
+        Some(command) => command_preview(command, item),
+        None => path_preview(Path::new(item)),
+    };
+    text.lines()
+        .take(MAX_PREVIEW_LINES)
+        .map(|line| line.replace('\t', "    "))
+        .collect()
+}
+
+fn command_preview(command: &str, item: &str) -> String {
+    let command = command.replace("{}", &shell_quote(item));
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .stdin(Stdio::null())
+        .output();
+    match output {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            text
+        }
+        Err(err) => format!("running `{command}` failed: {err}"),
+    }
+}
+
+fn path_preview(path: &Path) -> String {
+    if path.is_dir() {
+        return match fs::read_dir(path) {
+            Ok(entries) => {
+                let mut names: Vec<String> = entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .collect();
+                names.sort();
+                names.join("\n")
+            }
+            Err(err) => err.to_string(),
+        };
+    }
+    if !path.is_file() {
+        return "no preview".to_string();
+    }
+    let mut content = Vec::new();
+    let read = fs::File::open(path)
+        .and_then(|file| file.take(MAX_PREVIEW_BYTES).read_to_end(&mut content));
+    match read {
+        Err(err) => err.to_string(),
+        Ok(_) if content.contains(&0) => "binary file".to_string(),
+        Ok(_) => String::from_utf8_lossy(&content).into_owned(),
+    }
+}
+
+/// Quote `s` for `sh`.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Generates previews on a background thread and caches them.
+///
+/// Requests arriving while a preview is generated replace each other, only
+/// the newest one is handled. The thread ends once the previewer is dropped.
+pub struct Previewer {
+    tx_request: Sender<String>,
+    rx_preview: Receiver<(String, Arc<Vec<String>>)>,
+    cache: HashMap<String, Arc<Vec<String>>>,
+    /// cached items, oldest first
+    cache_order: VecDeque<String>,
+    pending: Option<String>,
+}
+
+impl Previewer {
+    pub fn start(command: Option<String>) -> Self {
+        let (tx_request, rx_request) = unbounded::<String>();
+        let (tx_preview, rx_preview) = unbounded();
+        thread::spawn(move || {
+            while let Ok(mut item) = rx_request.recv() {
+                if let Some(newer) = rx_request.try_iter().last() {
+                    item = newer;
+                }
+                let preview = generate_preview(command.as_deref(), &item);
+                if tx_preview.send((item, Arc::new(preview))).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            tx_request,
+            rx_preview,
+            cache: HashMap::new(),
+            cache_order: VecDeque::new(),
+            pending: None,
+        }
+    }
+
+    /// Ask for the preview of `item` unless it is cached or already requested.
+    pub fn request(&mut self, item: &str) {
+        if self.cache.contains_key(item) || self.pending.as_deref() == Some(item) {
+            return;
+        }
+        self.pending = Some(item.to_string());
+        let _ = self.tx_request.send(item.to_string());
+    }
+
+    /// Move finished previews into the cache, true if there were any.
+    pub fn receive(&mut self) -> bool {
+        let mut received = false;
+        for (item, preview) in self.rx_preview.try_iter() {
+            if self.pending.as_ref() == Some(&item) {
+                self.pending = None;
+            }
+            if self.cache.insert(item.clone(), preview).is_none() {
+                self.cache_order.push_back(item);
+            }
+            received = true;
+        }
+        while self.cache_order.len() > PREVIEW_CACHE_SIZE {
+            if let Some(oldest) = self.cache_order.pop_front() {
+                self.cache.remove(&oldest);
+            }
+        }
+        received
+    }
+
+    pub fn get(&self, item: &str) -> Option<Arc<Vec<String>>> {
+        self.cache.get(item).cloned()
+    }
+}
@@ -0,0 +1,342 @@
+use crate::finder::{
+    matcher::{substring_indices, Anchor, CaseMode},
+    normalize::{fold, fold_pattern, fold_to_base, Folded},
+};
+use fuzzy_matcher::FuzzyMatcher;
+
+/// How a single query term is matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermKind {
+    /// plain term, matched with the active matcher
+    Fuzzy,
+    /// `'term`
+    Exact,
+    /// `^term`
+    Prefix,
+    /// `term$`
+    Suffix,
+    /// `^term$`
+    Line,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub kind: TermKind,
+    /// `!term`: the item must not contain the term
+    pub negated: bool,
+    pub text: String,
+    /// `--normalize`: the term with umlauts spelled out, matched besides `text`
+    pub spelled: Option<String>,
+}
+
+impl Term {
+    /// Parse a single token like `'exact`, `^prefix`, `suffix$` or `!negation`.
+    fn parse(token: &str) -> Option<Self> {
+        let (negated, token) = match token.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, token),
+        };
+        let (kind, text) = if let Some(rest) = token.strip_prefix('\'') {
+            (TermKind::Exact, rest)
+        } else {
+            let (prefix, rest) = match token.strip_prefix('^') {
+                Some(rest) => (true, rest),
+                None => (false, token),
+            };
+            let (suffix, rest) = match rest.strip_suffix('$') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, rest),
+            };
+            let kind = match (prefix, suffix) {
+                (true, true) => TermKind::Line,
+                (true, false) => TermKind::Prefix,
+                (false, true) => TermKind::Suffix,
+                // like fzf a negated plain term is an inverse exact match
+                (false, false) if negated => TermKind::Exact,
+                (false, false) => TermKind::Fuzzy,
+            };
+            (kind, rest)
+        };
+        (!text.is_empty()).then(|| Self {
+            kind,
+            negated,
+            text: text.to_string(),
+            spelled: None,
+        })
+    }
+
+    /// `(score, indices)` of the term in `item`, ignoring negation.
+    fn find(
+        &self,
+        matcher: &dyn FuzzyMatcher,
+        item: &str,
+        case: CaseMode,
+    ) -> Option<(i64, Vec<usize>)> {
+        std::iter::once(&self.text)
+            .chain(&self.spelled)
+            .filter_map(|text| self.find_text(matcher, item, text, case))
+            .max_by_key(|(score, _)| *score)
+    }
+
+    fn find_text(
+        &self,
+        matcher: &dyn FuzzyMatcher,
+        item: &str,
+        text: &str,
+        case: CaseMode,
+    ) -> Option<(i64, Vec<usize>)> {
+        let anchor = match self.kind {
+            TermKind::Fuzzy => return matcher.fuzzy_indices(item, text),
+            TermKind::Exact => Anchor::Anywhere,
+            TermKind::Prefix => Anchor::Start,
+            TermKind::Suffix => Anchor::End,
+            TermKind::Line => Anchor::Line,
+        };
+        substring_indices(item, text, anchor, case)
+    }
+}
+
+/// fzf style extended search query.
+///
+/// Space separated terms must all match, terms joined by ` | ` form a group
+/// of which one has to match. The scores of all terms are summed up and
+/// their indices merged, so every matched term gets highlighted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    groups: Vec<Vec<Term>>,
+    /// case sensitivity of the exact, prefix and suffix terms
+    case: CaseMode,
+    /// `--normalize`: terms and items are matched with accents folded
+    normalize: bool,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        let mut groups: Vec<Vec<Term>> = Vec::new();
+        let mut continue_group = false;
+        for token in tokenize(query) {
+            if token == "|" {
+                continue_group = !groups.is_empty();
+                continue;
+            }
+            let Some(term) = Term::parse(&token) else {
+                continue;
+            };
+            match groups.last_mut() {
+                Some(group) if continue_group => group.push(term),
+                _ => groups.push(vec![term]),
+            }
+            continue_group = false;
+        }
+        Self {
+            groups,
+            ..Self::default()
+        }
+    }
+
+    /// The whole query as one term for the active matcher, used for regex
+    /// queries where spaces and `|` are part of the pattern.
+    pub fn literal(query: &str) -> Self {
+        let groups = if query.is_empty() {
+            Vec::new()
+        } else {
+            vec![vec![Term {
+                kind: TermKind::Fuzzy,
+                negated: false,
+                text: query.to_string(),
+                spelled: None,
+            }]]
+        };
+        Self {
+            groups,
+            ..Self::default()
+        }
+    }
+
+    pub fn case(mut self, case: CaseMode) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Fold accents of terms and items before matching, see [`fold_pattern`]
+    /// and [`fold`]. The indices still refer to the chars of the original item.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        if normalize && !self.normalize {
+            for term in self.groups.iter_mut().flatten() {
+                (term.text, term.spelled) = fold_pattern(&term.text);
+            }
+        }
+        self.normalize = normalize;
+        self
+    }
+
+    pub fn match_indices(
+        &self,
+        matcher: &dyn FuzzyMatcher,
+        item: &str,
+    ) -> Option<(i64, Vec<usize>)> {
+        if self.normalize {
+            // umlauts match their spelled out and their base letters
+            let spelled = fold(item);
+            let base = spelled
+                .as_ref()
+                .and_then(|spelled| fold_to_base(item).filter(|base| base.text != spelled.text));
+            let folds: Vec<Folded> = [spelled, base].into_iter().flatten().collect();
+            if !folds.is_empty() {
+                return folds
+                    .into_iter()
+                    .filter_map(|folded| {
+                        let (score, indices) = self.match_terms(matcher, &folded.text)?;
+                        Some((score, folded.original_indices(indices)))
+                    })
+                    .max_by_key(|(score, _)| *score);
+            }
+        }
+        self.match_terms(matcher, item)
+    }
+
+    fn match_terms(&self, matcher: &dyn FuzzyMatcher, item: &str) -> Option<(i64, Vec<usize>)> {
+        let mut score = 0;
+        let mut indices = Vec::new();
+        for group in &self.groups {
+            let (term_score, term_indices) = group.iter().find_map(|term| {
+                match (term.find(matcher, item, self.case), term.negated) {
+                    (Some(found), false) => Some(found),
+                    (None, true) => Some((0, Vec::new())),
+                    _ => None,
+                }
+            })?;
+            score += term_score;
+            indices.extend(term_indices);
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        Some((score, indices))
+    }
+}
+
+/// Split at whitespace, `\ ` is a literal space.
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&' ') => {
+                token.push(' ');
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finder::matcher::MatcherKind;
+
+    fn term(kind: TermKind, negated: bool, text: &str) -> Term {
+        Term {
+            kind,
+            negated,
+            text: text.to_string(),
+            spelled: None,
+        }
+    }
+
+    fn matches(query: &str, item: &str) -> Option<Vec<usize>> {
+        let matcher = MatcherKind::Skim.build(CaseMode::Smart);
+        Query::parse(query)
+            .match_indices(&*matcher, item)
+            .map(|(_, indices)| indices)
+    }
+
+    #[test]
+    fn parse_term_kinds() {
+        use TermKind::*;
+        let query = Query::parse("fuz 'exact ^pre suf$ ^line$ !not !^nopre $");
+        let terms: Vec<Term> = query.groups.into_iter().flatten().collect();
+        assert_eq!(
+            terms,
+            [
+                term(Fuzzy, false, "fuz"),
+                term(Exact, false, "exact"),
+                term(Prefix, false, "pre"),
+                term(Suffix, false, "suf"),
+                term(Line, false, "line"),
+                term(Exact, true, "not"),
+                term(Prefix, true, "nopre"),
+                // a lone `$` is a fuzzy term, not an empty suffix
+                term(Fuzzy, false, "$"),
+            ]
+        );
+        // markers alone are no terms
+        assert_eq!(Query::parse("' ^ !"), Query::default());
+    }
+
+    #[test]
+    fn parse_or_groups_and_escaped_spaces() {
+        let query = Query::parse("| a | b c\\ d |");
+        assert_eq!(
+            query.groups,
+            [
+                vec![
+                    term(TermKind::Fuzzy, false, "a"),
+                    term(TermKind::Fuzzy, false, "b"),
+                ],
+                vec![term(TermKind::Fuzzy, false, "c d")],
+            ]
+        );
+    }
+
+    #[test]
+    fn all_groups_must_match() {
+        assert_eq!(
+            matches("^git 'stat", "git status"),
+            Some(vec![0, 1, 2, 4, 5, 6, 7])
+        );
+        assert_eq!(matches("^git !stat", "git status"), None);
+        assert_eq!(matches("^git !log", "git status"), Some(vec![0, 1, 2]));
+        assert_eq!(matches("log | tus$", "git status"), Some(vec![7, 8, 9]));
+        assert_eq!(matches("^status$", "git status"), None);
+        assert_eq!(matches("", "anything"), Some(vec![]));
+    }
+
+    #[test]
+    fn normalized_umlauts_match_spelled_out() {
+        let item = "Goedel Escher, Bach";
+        for (kind, query) in [
+            (MatcherKind::Skim, "Gödel"),
+            (MatcherKind::Skim, "'Gödel"),
+            (MatcherKind::Skim, "^Gödel"),
+            (MatcherKind::Exact, "Gödel"),
+            (MatcherKind::Prefix, "Gödel"),
+        ] {
+            let matcher = kind.build(CaseMode::Smart);
+            let query = Query::parse(query).normalize(true);
+            let (_, indices) = query
+                .match_indices(&*matcher, item)
+                .unwrap_or_else(|| panic!("{kind} {query:?}"));
+            assert_eq!(indices, [0, 1, 2, 3, 4, 5], "{kind} {query:?}");
+            assert!(Query::parse("'Gödel")
+                .match_indices(&*matcher, item)
+                .is_none());
+        }
+        let exact = MatcherKind::Exact.build(CaseMode::Smart);
+        for query in ["Gödel", "Godel", "Goedel"] {
+            let query = Query::parse(query).normalize(true);
+            let found = query.match_indices(&*exact, "Kurt Gödel");
+            assert_eq!(found.map(|(_, indices)| indices), Some(vec![5, 6, 7, 8, 9]));
+        }
+    }
+}
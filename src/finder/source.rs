@@ -0,0 +1,184 @@
+use crate::finder::item_store::ItemStore;
+use color_eyre::{eyre::WrapErr, Result};
+use std::{
+    env, fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, IsTerminal, Read},
+    os::unix::process::CommandExt,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::Arc,
+    thread,
+};
+
+/// command used when neither a file, a command nor piped stdin is given
+pub const DEFAULT_COMMAND: &str = "find . -type f";
+/// environment variable overriding [`DEFAULT_COMMAND`]
+pub const DEFAULT_COMMAND_ENV: &str = "FUZZY_FINDER_DEFAULT_COMMAND";
+
+/// Where the candidates come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Stdin,
+    File(PathBuf),
+    /// shell command, its stdout is read line by line
+    Command(String),
+}
+
+impl Source {
+    /// Piped stdin if there is any, otherwise the default command.
+    pub fn detect() -> Self {
+        if io::stdin().is_terminal() {
+            let command =
+                env::var(DEFAULT_COMMAND_ENV).unwrap_or_else(|_| DEFAULT_COMMAND.to_string());
+            Source::Command(command)
+        } else {
+            Source::Stdin
+        }
+    }
+
+    /// File name safe key, so each source keeps its own history.
+    pub fn namespace(&self) -> String {
+        let (kind, name) = match self {
+            Source::Stdin => return "stdin".to_string(),
+            Source::File(path) => ("file", path.display().to_string()),
+            Source::Command(command) => ("command", command.clone()),
+        };
+        let name: String = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .take(100)
+            .collect();
+        format!("{kind}-{name}")
+    }
+
+    /// Read the candidates on a background thread, appending them to `store`
+    /// as they arrive. Errors opening the source are returned right away,
+    /// errors while reading are recorded in the store.
+    ///
+    /// Returns the process of a command, which stops the reader when dropped.
+    pub fn spawn_reader(&self, store: Arc<ItemStore>) -> Result<Option<SourceProcess>> {
+        let (reader, child): (Box<dyn Read + Send>, _) = match self {
+            Source::Stdin => (Box::new(io::stdin()), None),
+            Source::File(path) => {
+                let file =
+                    File::open(path).wrap_err_with(|| format!("opening {}", path.display()))?;
+                (Box::new(file), None)
+            }
+            Source::Command(command) => {
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .process_group(0)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .wrap_err_with(|| format!("running `{command}`"))?;
+                let stdout = child.stdout.take().expect("stdout is piped");
+                (Box::new(stdout), Some(child))
+            }
+        };
+        let name = self.to_string();
+        thread::spawn(move || {
+            let result = read_lines(BufReader::new(reader), &store);
+            store.finish(result.err().map(|err| format!("reading {name}: {err}")));
+        });
+        Ok(child.map(SourceProcess))
+    }
+}
+
+/// Command the candidates are read from. Killed and waited for when
+/// dropped, so reloading or quitting never leaves a slow or endless command
+/// running, and its reader sees the end of the output.
+#[derive(Debug)]
+pub struct SourceProcess(Child);
+
+impl Drop for SourceProcess {
+    fn drop(&mut self) {
+        // the command has its own process group, so what the shell started,
+        // like the parts of a pipeline, is stopped too
+        let _ = Command::new("kill")
+            .arg("--")
+            .arg(format!("-{}", self.0.id()))
+            .stderr(Stdio::null())
+            .status();
+        // fails if it exited already, waiting reaps it either way
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Stdin => write!(f, "stdin"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Command(command) => write!(f, "output of `{command}`"),
+        }
+    }
+}
+
+/// lines collected before they are added to the store
+const BATCH_SIZE: usize = 1024;
+
+/// Read non-empty lines, replacing invalid UTF-8 instead of failing.
+///
+/// Lines are added in batches, or as soon as no more input is buffered, so
+/// slow sources show up line by line and fast ones do not contend the lock.
+/// Reading stops once nobody but the reader holds the store, like after a
+/// reload replaced it.
+fn read_lines<R: Read>(mut reader: BufReader<R>, store: &Arc<ItemStore>) -> io::Result<()> {
+    let mut lines = Vec::new();
+    let mut buf = Vec::new();
+    while Arc::strong_count(store) > 1 && reader.read_until(b'\n', &mut buf)? > 0 {
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(['\n', '\r']);
+        if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+        buf.clear();
+        if lines.len() >= BATCH_SIZE || reader.buffer().is_empty() {
+            store.extend(lines.drain(..));
+        }
+    }
+    store.extend(lines);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let started = Instant::now();
+        while !condition() {
+            assert!(started.elapsed() < Duration::from_secs(5), "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn dropping_the_process_stops_an_endless_command() {
+        let store = Arc::new(ItemStore::loading());
+        let process = Source::Command("yes | cat".to_string())
+            .spawn_reader(store.clone())
+            .unwrap();
+        wait_for(|| !store.is_empty());
+        drop(process);
+        wait_for(|| !store.is_loading());
+    }
+
+    #[test]
+    fn reading_stops_once_the_store_is_dropped() {
+        let store = Arc::new(ItemStore::loading());
+        let _process = Source::Command("yes".to_string())
+            .spawn_reader(store.clone())
+            .unwrap();
+        wait_for(|| !store.is_empty());
+        let reader = Arc::downgrade(&store);
+        drop(store);
+        wait_for(|| reader.strong_count() == 0);
+    }
+}
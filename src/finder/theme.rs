@@ -0,0 +1,311 @@
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Report, Result,
+};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use std::{env, str::FromStr};
+use toml::{Table, Value};
+
+/// Built-in color schemes, `--color light`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Preset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    /// no colors, only bold, underlined and reversed text
+    Bw,
+}
+
+impl FromStr for Preset {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dark" => Ok(Preset::Dark),
+            "light" => Ok(Preset::Light),
+            "high-contrast" => Ok(Preset::HighContrast),
+            "bw" => Ok(Preset::Bw),
+            _ => bail!("unknown color scheme {s}, expected dark, light, high-contrast or bw"),
+        }
+    }
+}
+
+/// Part of the UI a `--color name:color` setting applies to, named like in fzf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Element {
+    /// text of the whole UI
+    Fg,
+    Bg,
+    /// matched chars
+    Hl,
+    /// selected result
+    FgSelected,
+    BgSelected,
+    Border,
+    /// titles on the borders
+    Label,
+    /// query text
+    Prompt,
+    /// `+` in front of marked results
+    Marker,
+    /// counters, status line and score column
+    Info,
+    PreviewFg,
+    PreviewBg,
+}
+
+impl Element {
+    const NAMES: [(&'static str, Element); 12] = [
+        ("fg", Element::Fg),
+        ("bg", Element::Bg),
+        ("hl", Element::Hl),
+        ("fg+", Element::FgSelected),
+        ("bg+", Element::BgSelected),
+        ("border", Element::Border),
+        ("label", Element::Label),
+        ("prompt", Element::Prompt),
+        ("marker", Element::Marker),
+        ("info", Element::Info),
+        ("preview-fg", Element::PreviewFg),
+        ("preview-bg", Element::PreviewBg),
+    ];
+
+    fn is_background(self) -> bool {
+        matches!(self, Element::Bg | Element::BgSelected | Element::PreviewBg)
+    }
+}
+
+impl FromStr for Element {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Self::NAMES.iter().find(|(name, _)| *name == s) {
+            Some((_, element)) => Ok(*element),
+            None => bail!("unknown color element {s}, see --help for the list of elements"),
+        }
+    }
+}
+
+/// One setting of a `--color` list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSetting {
+    /// start over from a built-in scheme
+    Preset(Preset),
+    /// `name:color:attribute...`, e.g. `hl:#ff8700:bold`
+    Element(Element, Option<Color>, Modifier),
+}
+
+impl FromStr for ColorSetting {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((name, value)) = s.split_once(':') else {
+            return Ok(ColorSetting::Preset(s.parse()?));
+        };
+        let element = name.parse()?;
+        let mut color = None;
+        let mut modifier = Modifier::empty();
+        for part in value.split(':') {
+            match part {
+                "bold" => modifier |= Modifier::BOLD,
+                "dim" => modifier |= Modifier::DIM,
+                "italic" => modifier |= Modifier::ITALIC,
+                "underline" => modifier |= Modifier::UNDERLINED,
+                "reverse" => modifier |= Modifier::REVERSED,
+                _ => {
+                    let parsed = part
+                        .parse()
+                        .map_err(|_| Report::msg(format!("invalid color {part} in {s}")))?;
+                    if color.replace(parsed).is_some() {
+                        bail!("more than one color in {s}");
+                    }
+                }
+            }
+        }
+        Ok(ColorSetting::Element(element, color, modifier))
+    }
+}
+
+/// Parse a `--color` list like `light,hl:red:bold,bg+:#303030`.
+pub fn parse_colors(spec: &str) -> Result<Vec<ColorSetting>> {
+    spec.split(',')
+        .filter(|setting| !setting.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Styles of the fuzzy finder UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub text: Style,
+    pub matched: Style,
+    pub selected: Style,
+    pub border: Style,
+    pub label: Style,
+    pub prompt: Style,
+    pub marker: Style,
+    pub info: Style,
+    pub preview: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(Preset::default())
+    }
+}
+
+impl Theme {
+    pub fn preset(preset: Preset) -> Self {
+        let plain = Self {
+            text: Style::new(),
+            matched: Style::new(),
+            selected: Style::new().reversed(),
+            border: Style::new(),
+            label: Style::new(),
+            prompt: Style::new(),
+            marker: Style::new().bold(),
+            info: Style::new().dim(),
+            preview: Style::new(),
+        };
+        match preset {
+            Preset::Dark => Self {
+                matched: Style::new().green(),
+                ..plain
+            },
+            Preset::Light => Self {
+                text: Style::new().black(),
+                matched: Style::new().blue().bold(),
+                selected: Style::new().black().on_gray(),
+                border: Style::new().dark_gray(),
+                marker: Style::new().magenta().bold(),
+                ..plain
+            },
+            Preset::HighContrast => {
+                let text = Style::new().white().on_black();
+                Self {
+                    text,
+                    matched: Style::new().yellow().bold().underlined(),
+                    selected: Style::new().black().on_white().bold(),
+                    border: Style::new().white(),
+                    label: Style::new().white().bold(),
+                    prompt: Style::new().white().bold(),
+                    marker: Style::new().yellow().bold(),
+                    info: Style::new().white(),
+                    preview: text,
+                }
+            }
+            Preset::Bw => Self {
+                matched: Style::new().bold().underlined(),
+                ..plain
+            },
+        }
+    }
+
+    /// The default scheme, or no colors if `NO_COLOR` is set.
+    pub fn from_env() -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self::preset(if no_color { Preset::Bw } else { Preset::Dark })
+    }
+
+    pub fn apply(&mut self, setting: ColorSetting) {
+        let (element, color, modifier) = match setting {
+            ColorSetting::Preset(preset) => {
+                *self = Self::preset(preset);
+                return;
+            }
+            ColorSetting::Element(element, color, modifier) => (element, color, modifier),
+        };
+        let style = match element {
+            Element::Fg | Element::Bg => &mut self.text,
+            Element::Hl => &mut self.matched,
+            Element::FgSelected | Element::BgSelected => &mut self.selected,
+            Element::Border => &mut self.border,
+            Element::Label => &mut self.label,
+            Element::Prompt => &mut self.prompt,
+            Element::Marker => &mut self.marker,
+            Element::Info => &mut self.info,
+            Element::PreviewFg | Element::PreviewBg => &mut self.preview,
+        };
+        if color.is_some() && matches!(element, Element::FgSelected | Element::BgSelected) {
+            // a colored selection is not reversed, the colors would swap
+            style.add_modifier.remove(Modifier::REVERSED);
+        }
+        *style = match color {
+            Some(color) if element.is_background() => style.bg(color),
+            Some(color) => style.fg(color),
+            None => *style,
+        }
+        .add_modifier(modifier);
+    }
+
+    /// Apply the `[colors]` table of the config file, `preset` first.
+    pub fn apply_toml(&mut self, colors: Table) -> Result<()> {
+        let mut settings = Vec::new();
+        for (name, value) in colors {
+            let Value::String(value) = value else {
+                bail!("colors.{name} expects a color in quotes");
+            };
+            let setting = if name == "preset" {
+                ColorSetting::Preset(value.parse()?)
+            } else {
+                format!("{name}:{value}")
+                    .parse()
+                    .wrap_err_with(|| format!("invalid colors.{name}"))?
+            };
+            settings.push(setting);
+        }
+        settings.sort_by_key(|setting| !matches!(setting, ColorSetting::Preset(_)));
+        for setting in settings {
+            self.apply(setting);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_settings() {
+        let settings = parse_colors("light,hl:#ff8700:bold,bg+:208").unwrap();
+        assert_eq!(
+            settings,
+            [
+                ColorSetting::Preset(Preset::Light),
+                ColorSetting::Element(Element::Hl, Some(Color::Rgb(255, 135, 0)), Modifier::BOLD),
+                ColorSetting::Element(
+                    Element::BgSelected,
+                    Some(Color::Indexed(208)),
+                    Modifier::empty()
+                ),
+            ]
+        );
+        assert!(parse_colors("hl:red:blue").is_err());
+        assert!(parse_colors("pointer:red").is_err());
+        assert!(parse_colors("solarized").is_err());
+    }
+
+    #[test]
+    fn apply_settings_in_order() {
+        let mut theme = Theme::default();
+        for setting in parse_colors("hl:red,bw,marker:yellow,bg+:blue").unwrap() {
+            theme.apply(setting);
+        }
+        // the preset replaced the earlier hl color
+        assert_eq!(theme.matched, Theme::preset(Preset::Bw).matched);
+        assert_eq!(theme.marker, Style::new().bold().yellow());
+        // a colored selection is not reversed
+        assert_eq!(theme.selected, Style::new().on_blue());
+    }
+
+    #[test]
+    fn config_table_applies_preset_first() {
+        let mut theme = Theme::default();
+        let colors: Table = "hl = \"red\"\npreset = \"light\"".parse().unwrap();
+        theme.apply_toml(colors).unwrap();
+        assert_eq!(theme.matched, Style::new().red().bold());
+        assert_eq!(theme.border, Theme::preset(Preset::Light).border);
+    }
+}
@@ -0,0 +1,316 @@
+use crate::finder::{
+    fields::Fields,
+    frecency::Ranking,
+    item_store::{ItemStore, Snapshot},
+    query::Query,
+};
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use fuzzy_matcher::FuzzyMatcher;
+use rayon::prelude::*;
+use std::{
+    cmp::Ordering,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// `(score, matched char indices, item index)`
+pub type MatchResult = (i64, Vec<usize>, usize);
+
+/// how often results are extended while items are still being read
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Match `query` against the items from index `start` on, best first.
+///
+/// Only the `--nth` fields are matched, the indices refer to the whole item.
+/// Chunks of items are matched in parallel on all cores. Returns `None` if
+/// `cancel` reports a newer query before matching finished.
+pub fn match_candidates(
+    matcher: &dyn FuzzyMatcher,
+    items: &Snapshot,
+    start: usize,
+    query: &Query,
+    ranking: Option<&Ranking>,
+    fields: &Fields,
+    cancel: impl Fn() -> bool + Sync,
+) -> Option<Vec<MatchResult>> {
+    let chunks: Option<Vec<Vec<MatchResult>>> = items
+        .par_chunks_from(start)
+        .map(|(offset, chunk)| {
+            if cancel() {
+                return None;
+            }
+            let results = chunk
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| {
+                    let (score, indices) =
+                        query.match_indices(matcher, &fields.match_text(item))?;
+                    let indices = fields.item_indices(item, indices);
+                    let boost = ranking.map_or(0, |ranking| ranking.boost(item));
+                    Some((score + boost, indices, offset + i))
+                })
+                .collect();
+            Some(results)
+        })
+        .collect();
+    let mut result_list: Vec<MatchResult> = chunks?.into_iter().flatten().collect();
+    result_list.par_sort_unstable_by(|a, b| compare_results(a, b, items, ranking));
+    Some(result_list)
+}
+
+/// Best score first. Equal scores are ordered by frecency if a `ranking`
+/// is given, then by input order.
+pub fn compare_results(
+    a: &MatchResult,
+    b: &MatchResult,
+    items: &Snapshot,
+    ranking: Option<&Ranking>,
+) -> Ordering {
+    b.0.cmp(&a.0)
+        .then_with(|| match (ranking, items.get(a.2), items.get(b.2)) {
+            (Some(ranking), Some(a_item), Some(b_item)) => ranking.compare(a_item, b_item),
+            _ => Ordering::Equal,
+        })
+        .then_with(|| a.2.cmp(&b.2))
+}
+
+/// Merge the results of newly read items into the sorted `result_list`.
+pub fn merge_results(
+    result_list: &[MatchResult],
+    new_results: Vec<MatchResult>,
+    items: &Snapshot,
+    ranking: Option<&Ranking>,
+) -> Vec<MatchResult> {
+    let mut merged = Vec::with_capacity(result_list.len() + new_results.len());
+    let mut old = result_list.iter().peekable();
+    for new in new_results {
+        while let Some(result) =
+            old.next_if(|result| compare_results(result, &new, items, ranking).is_lt())
+        {
+            merged.push(result.clone());
+        }
+        merged.push(new);
+    }
+    merged.extend(old.cloned());
+    merged
+}
+
+/// Results of a query over the first `searched` items.
+#[derive(Debug, Clone)]
+pub struct Matches {
+    pub generation: u64,
+    pub searched: usize,
+    pub results: Arc<Vec<MatchResult>>,
+    /// time spent matching, summed up over all items read so far
+    pub duration: Duration,
+}
+
+/// generation, query and matcher of a search
+type Request = (u64, Query, Arc<dyn FuzzyMatcher>);
+
+/// Background thread matching queries, so typing never waits for a search.
+///
+/// Each query carries a generation number which is sent back with its
+/// results, and the matcher to use. Queries arriving while a search runs
+/// cancel it, only the newest one is matched. While items are still read
+/// the results of the last query are extended by the new items. The thread
+/// ends once the worker is dropped.
+pub struct MatchWorker {
+    tx_query: Sender<Request>,
+    rx_results: Receiver<Matches>,
+}
+
+impl MatchWorker {
+    pub fn start(
+        store: Arc<ItemStore>,
+        ranking: Option<Arc<Ranking>>,
+        fields: Arc<Fields>,
+    ) -> Self {
+        let (tx_query, rx_query) = unbounded::<Request>();
+        let (tx_results, rx_results) = unbounded();
+        thread::spawn(move || {
+            let ranking = ranking.as_deref();
+            let cancel = || !rx_query.is_empty();
+            let mut current: Option<(Request, Matches)> = None;
+            loop {
+                // checked before the length, all items are read once loading stopped
+                let loading = store.is_loading();
+                let caught_up = current
+                    .as_ref()
+                    .is_none_or(|(_, matches)| matches.searched == store.len());
+                let request = if !loading && caught_up {
+                    rx_query.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                } else {
+                    rx_query.recv_timeout(UPDATE_INTERVAL)
+                };
+                let matches = match request {
+                    Ok(mut request) => {
+                        // skip everything but the newest query
+                        if let Some(newer) = rx_query.try_iter().last() {
+                            request = newer;
+                        }
+                        let items = store.snapshot();
+                        let (generation, query, matcher) = &request;
+                        let started = Instant::now();
+                        let Some(results) = match_candidates(
+                            &**matcher, &items, 0, query, ranking, &fields, cancel,
+                        ) else {
+                            continue;
+                        };
+                        let matches = Matches {
+                            generation: *generation,
+                            searched: items.len(),
+                            results: Arc::new(results),
+                            duration: started.elapsed(),
+                        };
+                        &current.insert((request, matches)).1
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        let Some(((generation, query, matcher), matches)) = &mut current else {
+                            continue;
+                        };
+                        let items = store.snapshot();
+                        if items.len() == matches.searched {
+                            continue;
+                        }
+                        let start = matches.searched;
+                        let started = Instant::now();
+                        let Some(new_results) = match_candidates(
+                            &**matcher, &items, start, query, ranking, &fields, cancel,
+                        ) else {
+                            continue;
+                        };
+                        let results = merge_results(&matches.results, new_results, &items, ranking);
+                        *matches = Matches {
+                            generation: *generation,
+                            searched: items.len(),
+                            results: Arc::new(results),
+                            duration: matches.duration + started.elapsed(),
+                        };
+                        matches
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                if tx_results.send(matches.clone()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            tx_query,
+            rx_results,
+        }
+    }
+
+    pub fn search(&self, generation: u64, query: Query, matcher: Arc<dyn FuzzyMatcher>) {
+        let _ = self.tx_query.send((generation, query, matcher));
+    }
+
+    /// Newest results received since the last call, if any.
+    pub fn try_recv(&self) -> Option<Matches> {
+        self.rx_results.try_iter().last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finder::frecency::Frecency;
+    use fuzzy_matcher::skim::SkimMatcherV2;
+    use std::time::SystemTime;
+
+    fn store(items: &[&str]) -> Arc<ItemStore> {
+        Arc::new(ItemStore::from_items(
+            items.iter().map(|item| item.to_string()).collect(),
+        ))
+    }
+
+    fn indices(results: &[MatchResult]) -> Vec<usize> {
+        results.iter().map(|result| result.2).collect()
+    }
+
+    /// Generations of all results received until `generation` arrives.
+    fn receive_until(worker: &MatchWorker, generation: u64) -> (Vec<u64>, Matches) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut generations = Vec::new();
+        while Instant::now() < deadline {
+            let Some(matches) = worker.try_recv() else {
+                thread::sleep(Duration::from_millis(5));
+                continue;
+            };
+            generations.push(matches.generation);
+            if matches.generation == generation {
+                return (generations, matches);
+            }
+        }
+        panic!("no results for generation {generation}, got {generations:?}");
+    }
+
+    #[test]
+    fn cancelled_matching_returns_nothing() {
+        let items = store(&["a", "ab"]).snapshot();
+        let query = Query::parse("a");
+        let matcher = SkimMatcherV2::default();
+        let fields = Fields::default();
+        let matched = match_candidates(&matcher, &items, 0, &query, None, &fields, || false);
+        assert_eq!(matched.map(|results| results.len()), Some(2));
+        let cancelled = match_candidates(&matcher, &items, 0, &query, None, &fields, || true);
+        assert_eq!(cancelled, None);
+    }
+
+    #[test]
+    fn equal_scores_are_ordered_by_frecency_then_input() {
+        let items = store(&["a", "b", "c", "d"]).snapshot();
+        let mut frecency = Frecency::default();
+        frecency.record("c", SystemTime::now());
+        let ranking = frecency.ranking(SystemTime::now());
+        let old = vec![(9, vec![], 3), (5, vec![], 0), (5, vec![], 2)];
+        let new = vec![(5, vec![], 1), (1, vec![], 2)];
+        let merged = merge_results(&old, new.clone(), &items, None);
+        assert_eq!(indices(&merged), [3, 0, 1, 2, 2]);
+        let mut old = old;
+        old.sort_by(|a, b| compare_results(a, b, &items, Some(&ranking)));
+        assert_eq!(indices(&old), [3, 2, 0]);
+        let merged = merge_results(&old, new, &items, Some(&ranking));
+        assert_eq!(indices(&merged), [3, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn only_the_newest_query_is_answered_last() {
+        let worker = MatchWorker::start(
+            store(&["apple", "banana", "cherry"]),
+            None,
+            Arc::new(Fields::default()),
+        );
+        let matcher: Arc<dyn FuzzyMatcher> = Arc::new(SkimMatcherV2::default());
+        for (generation, query) in [(1, "a"), (2, "an"), (3, "ch")] {
+            worker.search(generation, Query::parse(query), matcher.clone());
+        }
+        let (generations, matches) = receive_until(&worker, 3);
+        // older queries may be answered before the newest one, never after it
+        assert!(generations.is_sorted());
+        assert_eq!(indices(&matches.results), [2]);
+        assert_eq!(matches.searched, 3);
+    }
+
+    #[test]
+    fn results_are_extended_while_items_are_read() {
+        let items = Arc::new(ItemStore::loading());
+        items.extend(["one".to_string()]);
+        let worker = MatchWorker::start(items.clone(), None, Arc::new(Fields::default()));
+        let matcher: Arc<dyn FuzzyMatcher> = Arc::new(SkimMatcherV2::default());
+        worker.search(1, Query::parse("o"), matcher);
+        let (_, matches) = receive_until(&worker, 1);
+        assert_eq!(indices(&matches.results), [0]);
+        items.extend(["two".to_string(), "six".to_string()]);
+        items.finish(None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut matches = matches;
+        while matches.searched < 3 && Instant::now() < deadline {
+            matches = receive_until(&worker, 1).1;
+        }
+        assert_eq!(matches.generation, 1);
+        assert_eq!(indices(&matches.results), [0, 1]);
+    }
+}
@@ -1,211 +1,990 @@
 use color_eyre::Result;
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
+use crossterm::event::{
+    KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
-use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Position},
-    prelude::{Backend, Buffer, CrosstermBackend, Rect, Terminal},
-    style::{Color, Style, Stylize},
-    text::{Line, Span, Text},
-    widgets::{Block, List, ListItem, Paragraph, Widget},
+    layout::{Constraint, Layout, Margin, Position, Rect},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
     Frame,
 };
+use ratatui_examples::{
+    action::Action,
+    component::Component,
+    event::AppEvent,
+    finder::{
+        config::Config,
+        fields::Fields,
+        frecency::{Frecency, Ranking},
+        history::History,
+        item_store::ItemStore,
+        keymap::{expand_placeholders, KeyAction, Keymap},
+        line_editor::LineEditor,
+        matcher::{CaseMode, MatcherKind},
+        options::{Options, USAGE},
+        preview::{PreviewPosition, Previewer},
+        query::Query,
+        source::{Source, SourceProcess},
+        theme::Theme,
+        worker::{MatchResult, MatchWorker},
+    },
+    helper::Highlight,
+    runtime::Runtime,
+    script::run_script,
+    tui::{install_hooks, Output, Tui, TuiOptions},
+};
 use std::{
     env::args,
-    io::{self, stdout, Stdout},
+    ops::Range,
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
-mod helper;
-use helper::highlight_char_in_text;
-
-fn main() -> Result<()> {
-    color_eyre::install()?;
-    //    let terminal = ratatui::init();
-    let mut terminal = init_tui()?;
-    let mut app: App = App::new();
-    let app_result = app.run(&mut terminal);
-    restore_tui()?;
-    app_result
-}
 
-pub fn init_tui() -> io::Result<Terminal<impl Backend>> {
-    stdout().execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    Terminal::new(CrosstermBackend::new(stdout()))
-}
+fn main() -> Result<ExitCode> {
+    install_hooks()?;
+    let options = Options::parse(args().skip(1))?;
+    if options.help {
+        println!("{USAGE}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut config = Config::load(options.config.as_deref())?;
+    for (chord, action) in &options.bindings {
+        config.keymap.bind(*chord, action.clone());
+    }
+    for setting in &options.colors {
+        config.theme.apply(*setting);
+    }
+    let items = Arc::new(ItemStore::loading());
+    let process = options.source.spawn_reader(items.clone())?;
+    let history = if options.history {
+        History::load(&options.source.namespace(), options.history_size)?
+    } else {
+        History::in_memory(options.history_size)
+    };
+    let mut frecency = if options.frecency {
+        Some(Frecency::load(&options.source.namespace())?)
+    } else {
+        None
+    };
+    let ranking = frecency
+        .as_ref()
+        .map(|frecency| Arc::new(frecency.ranking(SystemTime::now())));
+
+    if let Some(script) = &options.script {
+        // only the screens are printed, history and frecency are left alone
+        let mut app = App::new(items, &options, history, ranking, config);
+        app.process = process;
+        run_script(script, &mut app)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // stdout is reserved for the selection, keys are read from the tty
+    let mut tui = Tui::with_options(TuiOptions {
+        output: Output::Stderr,
+        height: options.height,
+        mouse: options.mouse,
+    })?;
+    let mut app: App = App::new(items, &options, history, ranking, config);
+    app.process = process;
+    let app_result = Runtime::new().tick_rate(30.0).run(&mut tui, &mut app);
+    tui.restore()?;
+    app_result?;
+    // a reload replaced the items read at the start
+    if let Some(err) = app.items.error() {
+        eprintln!("fuzzy_finder: {err}");
+    }
+    if let Some(selection) = &app.selection {
+        if let Err(err) = app.history.save() {
+            eprintln!("fuzzy_finder: could not save the query history: {err}");
+        }
+        if let Some(frecency) = &mut frecency {
+            for item in selection {
+                frecency.record(item, SystemTime::now());
+            }
+            if let Err(err) = frecency.save() {
+                eprintln!("fuzzy_finder: could not save the frecency ranking: {err}");
+            }
+        }
+    }
 
-pub fn restore_tui() -> io::Result<()> {
-    stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
-    Ok(())
+    match app.selection {
+        Some(selection) if !selection.is_empty() => {
+            for item in selection {
+                println!("{}", options.fields.accept(&item));
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(_) => Ok(ExitCode::from(EXIT_NO_SELECTION)),
+        None => Ok(ExitCode::from(EXIT_ABORTED)),
+    }
 }
 
+const EXIT_NO_SELECTION: u8 = 1;
+const EXIT_ABORTED: u8 = 130;
+
 struct App {
-    should_exit: bool,
-    search_input: String,
-    search_input_character_index: usize,
-    book_list: Vec<String>,
-    result_list: Vec<(i64, Vec<usize>, String)>,
+    search_input: LineEditor,
+    /// key bindings from the defaults, the config file and `--bind`
+    keymap: Keymap,
+    /// styles from `NO_COLOR`, the config file and `--color`
+    theme: Theme,
+    history: History,
+    /// candidates, still growing while the source is read
+    items: Arc<ItemStore>,
+    /// command the items are read from, stopped on reload and exit
+    process: Option<SourceProcess>,
+    /// fields which are matched and shown
+    fields: Arc<Fields>,
+    /// `--frecency` ranking, kept to restart the worker on reload
+    ranking: Option<Arc<Ranking>>,
+    result_list: Arc<Vec<MatchResult>>,
+    result_state: ListState,
+    /// visible rows of the result list, for PageUp/PageDown
+    result_page_size: usize,
+    matcher_kind: MatcherKind,
+    matcher: Arc<dyn FuzzyMatcher>,
+    case: CaseMode,
+    /// `--normalize`: accents are ignored
+    normalize: bool,
+    /// matches off the UI thread
+    worker: MatchWorker,
+    /// incremented per query, results of older queries are dropped
+    search_generation: u64,
+    /// generation of the shown results
+    result_generation: u64,
+    /// time the worker spent matching the shown results
+    search_duration: Duration,
+    /// frame of the loading spinner
+    spinner: usize,
+    /// debug column with score, matched positions and matcher per result
+    show_scores: bool,
+    previewer: Previewer,
+    preview_visible: bool,
+    preview_position: PreviewPosition,
+    /// first shown line of the preview
+    preview_scroll: usize,
+    /// `--multi`: entries can be marked with Tab
+    multi: bool,
+    /// indices of the marked entries in the order they were marked
+    marked: Vec<usize>,
+    /// accepted entries, `None` while running or when aborted
+    selection: Option<Vec<String>>,
+    /// areas of the last render, for mouse clicks
+    input_area: Rect,
+    result_area: Rect,
+    preview_area: Option<Rect>,
+    /// columns the input is scrolled to the left
+    input_scroll: u16,
+    /// time and result of the last click, for double clicks
+    last_click: Option<(Instant, usize)>,
 }
 
 impl App {
-    fn new() -> Self {
-        let mut book_list = Vec::new();
-        book_list.push("Short History of Time".to_string());
-        book_list.push("War and Piece".to_string());
-        book_list.push("Mobby Dick".to_string());
-        book_list.push("Midsummer Dream".to_string());
-        book_list.push("The Picture of Dorian Gray".to_string());
-        book_list.push("Goedel Escher, Bach".to_string());
-        book_list.push("The Yellow King".to_string());
-        book_list.push("Rashomon".to_string());
-        book_list.push("High Fidelity".to_string());
-        book_list.push("A long Way down".to_string());
-        book_list.push("Hanami".to_string());
-        book_list.push("Entangled Life".to_string());
-        book_list.push("Guns, Germs and Steel".to_string());
-        book_list.push("Ready Player One".to_string());
-        book_list.push("Lost Music".to_string());
-        book_list.push("The Book of Drexciya".to_string());
-        book_list.push("War and Piece".to_string());
-        book_list.push("My Catalonia".to_string());
-        book_list.push("Art of Programming".to_string());
-        book_list.push("Who rules the World".to_string());
+    fn new(
+        items: Arc<ItemStore>,
+        options: &Options,
+        history: History,
+        ranking: Option<Arc<Ranking>>,
+        config: Config,
+    ) -> Self {
+        let fields = Arc::new(options.fields.clone());
+        let worker = MatchWorker::start(items.clone(), ranking.clone(), fields.clone());
+        let mut app = Self {
+            items,
+            process: None,
+            fields,
+            ranking,
+            search_input: LineEditor::default(),
+            keymap: config.keymap,
+            theme: config.theme,
+            history,
+            result_list: Arc::default(),
+            result_state: ListState::default(),
+            result_page_size: 1,
+            matcher_kind: options.matcher,
+            matcher: options.matcher.build(options.case),
+            case: options.case,
+            normalize: options.normalize,
+            worker,
+            search_generation: 0,
+            result_generation: 0,
+            search_duration: Duration::ZERO,
+            spinner: 0,
+            show_scores: options.scores,
+            previewer: Previewer::start(options.preview_command.clone()),
+            preview_visible: options.preview_window.is_some(),
+            preview_position: options.preview_window.unwrap_or_default(),
+            preview_scroll: 0,
+            multi: options.multi,
+            marked: Vec::new(),
+            selection: None,
+            input_area: Rect::default(),
+            result_area: Rect::default(),
+            preview_area: None,
+            input_scroll: 0,
+            last_click: None,
+        };
+        // an empty search lists all candidates
+        app.submit_search();
+        app
+    }
 
-        Self {
-            should_exit: false,
-            book_list,
-            search_input: "".to_string(),
-            search_input_character_index: 0,
-            result_list: Vec::new(),
+    /// Search for the current input, called after every edit.
+    fn submit_search(&mut self) {
+        self.search_generation += 1;
+        let query = match self.matcher_kind {
+            MatcherKind::Regex => Query::literal(self.search_input.text()),
+            _ => Query::parse(self.search_input.text()),
         }
+        .case(self.case)
+        .normalize(self.normalize);
+        self.worker
+            .search(self.search_generation, query, self.matcher.clone());
     }
 
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.search_input_character_index.saturating_sub(1);
-        self.search_input_character_index = self.clamp_cursor(cursor_moved_left);
+    /// Put a query recalled from the history into the input.
+    fn recall(&mut self, query: Option<String>) {
+        if let Some(query) = query {
+            self.search_input.set_text(&query);
+            self.submit_search();
+        }
     }
 
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.search_input_character_index.saturating_add(1);
-        self.search_input_character_index = self.clamp_cursor(cursor_moved_right);
+    /// Switch to the next matching algorithm and search again.
+    fn next_matcher(&mut self) {
+        self.matcher_kind = self.matcher_kind.next();
+        self.matcher = self.matcher_kind.build(self.case);
+        self.submit_search();
     }
 
-    fn enter_char(&mut self, new_char: char) {
-        let index = self.byte_index();
-        self.search_input.insert(index, new_char);
-        self.move_cursor_right();
+    /// Replace the results, a new query selects the best match.
+    fn set_result_list(&mut self, generation: u64, result_list: Arc<Vec<MatchResult>>) {
+        self.result_list = result_list;
+        let selected = if generation == self.result_generation {
+            // more items were read, keep the selected row
+            let selected = self.result_state.selected().unwrap_or(0);
+            Some(selected.min(self.result_list.len().saturating_sub(1)))
+        } else {
+            self.preview_scroll = 0;
+            *self.result_state.offset_mut() = 0;
+            Some(0)
+        };
+        self.result_state
+            .select(selected.filter(|_| !self.result_list.is_empty()));
+        self.result_generation = generation;
     }
 
-    fn byte_index(&self) -> usize {
-        self.search_input
-            .char_indices()
-            .map(|(i, _)| i)
-            .nth(self.search_input_character_index)
-            .unwrap_or(self.search_input.len())
+    /// Move the selection by `offset` rows, clamped to the result list.
+    fn move_selection(&mut self, offset: isize) {
+        let Some(last) = self.result_list.len().checked_sub(1) else {
+            return;
+        };
+        let current = self.result_state.selected().unwrap_or(0);
+        let selected = current.saturating_add_signed(offset).min(last);
+        self.result_state.select(Some(selected));
+        self.preview_scroll = 0;
     }
 
-    fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.search_input_character_index != 0;
-        if is_not_cursor_leftmost {
-            let current_index = self.search_input_character_index;
-            let from_left_to_current_index = current_index - 1;
+    fn page_offset(&self) -> isize {
+        self.result_page_size.max(1) as isize
+    }
 
-            let before_char_to_delete = self.search_input.chars().take(from_left_to_current_index);
-            let after_char_to_delete = self.search_input.chars().skip(current_index);
+    /// Take results of the current query from the worker, if there are any.
+    fn receive_results(&mut self) -> bool {
+        let Some(matches) = self.worker.try_recv() else {
+            return false;
+        };
+        if matches.generation != self.search_generation {
+            return false;
+        }
+        self.set_result_list(matches.generation, matches.results);
+        self.search_duration = matches.duration;
+        true
+    }
 
-            self.search_input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+    fn toggle_preview(&mut self) {
+        self.preview_visible = !self.preview_visible;
+    }
+
+    fn scroll_preview(&mut self, offset: isize) {
+        self.preview_scroll = self.preview_scroll.saturating_add_signed(offset);
+    }
+
+    /// Request the preview of the selected result, true if new previews arrived.
+    fn update_preview(&mut self) -> bool {
+        if !self.preview_visible {
+            return false;
+        }
+        if let Some(item) = self.selected_item() {
+            self.previewer.request(&item);
         }
+        self.previewer.receive()
     }
 
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.search_input.chars().count())
+    /// Item index of the selected result.
+    fn selected_index(&self) -> Option<usize> {
+        self.result_state
+            .selected()
+            .and_then(|i| self.result_list.get(i))
+            .map(|(_, _, index)| *index)
     }
 
-    fn reset_cursor(&mut self) {
-        self.search_input_character_index = 0;
+    fn selected_item(&self) -> Option<String> {
+        self.selected_index()
+            .and_then(|index| self.items.get(index))
     }
 
-    fn submit_search(&mut self) {
-        //self.search(self.input.clone());
-        let search_string = self.search_input.clone();
-        self.search_input.clear();
-        self.reset_cursor();
-
-        // doing matcher stuff
-        let matcher = SkimMatcherV2::default();
-        let mut result_list: Vec<(i64, Vec<usize>, String)> = Vec::new();
-        for item in self.book_list.iter() {
-            if let Some((score, indices)) = matcher.fuzzy_indices(item, &search_string) {
-                result_list.push((score, indices, item.clone()));
+    /// Toggle the mark of the selected result and move on by `offset`.
+    fn toggle_mark(&mut self, offset: isize) {
+        if !self.multi {
+            return;
+        }
+        if let Some(index) = self.selected_index() {
+            match self.marked.iter().position(|marked| *marked == index) {
+                Some(position) => {
+                    self.marked.remove(position);
+                }
+                None => self.marked.push(index),
             }
         }
-        result_list.sort_by(|(s1score, _, _), (s2score, _, _)| s2score.cmp(s1score));
-        self.result_list = result_list;
+        self.move_selection(offset);
     }
 
-    fn run(&mut self, terminal: &mut Terminal<impl Backend>) -> Result<()> {
-        while !self.should_exit {
-            terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
+    /// The marked results, or the selected one if none are marked.
+    fn marked_items(&self) -> Vec<String> {
+        if self.marked.is_empty() {
+            self.selected_item().into_iter().collect()
+        } else {
+            let items = self.items.snapshot();
+            self.marked
+                .iter()
+                .filter_map(|&index| items.get(index).map(str::to_string))
+                .collect()
         }
-        Ok(())
     }
 
-    fn handle_events(&mut self) -> Result<()> {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press
-                && key.code == KeyCode::Char('c')
-                && key.modifiers == KeyModifiers::CONTROL
-            {
-                self.should_exit = true;
-            } else {
-                match key.code {
-                    KeyCode::Enter => self.submit_search(),
-                    KeyCode::Char(to_insert) => self.enter_char(to_insert),
-                    KeyCode::Backspace => self.delete_char(),
-                    KeyCode::Left => self.move_cursor_left(),
-                    KeyCode::Right => self.move_cursor_right(),
-                    _ => {}
+    /// Accept the marked results, or the selected one if none are marked, and leave.
+    fn accept(&mut self) -> Action {
+        self.selection = Some(self.marked_items());
+        self.history.add(self.search_input.text());
+        Action::Quit
+    }
+
+    /// Wheel scrolls the results or the preview, a click selects a result
+    /// or places the input cursor, a double click accepts.
+    fn handle_mouse(&mut self, mouse: &MouseEvent) -> Option<Action> {
+        let position = Position::new(mouse.column, mouse.row);
+        let over_preview = self
+            .preview_area
+            .is_some_and(|area| area.contains(position));
+        match mouse.kind {
+            MouseEventKind::ScrollUp if over_preview => self.scroll_preview(-1),
+            MouseEventKind::ScrollDown if over_preview => self.scroll_preview(1),
+            MouseEventKind::ScrollUp => self.move_selection(-1),
+            MouseEventKind::ScrollDown => self.move_selection(1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(selected) = self.result_at(position) {
+                    let double_click = self.last_click.is_some_and(|(time, clicked)| {
+                        clicked == selected && time.elapsed() < DOUBLE_CLICK_TIME
+                    });
+                    if self.result_state.selected() != Some(selected) {
+                        self.result_state.select(Some(selected));
+                        self.preview_scroll = 0;
+                    }
+                    if double_click {
+                        self.last_click = None;
+                        return Some(self.accept());
+                    }
+                    self.last_click = Some((Instant::now(), selected));
+                } else if inner(self.input_area).contains(position) {
+                    let column = position.x - self.input_area.x - 1 + self.input_scroll;
+                    self.search_input.set_cursor_column(usize::from(column));
                 }
             }
+            _ => {}
         }
+        None
+    }
+
+    /// `execute(CMD)` for the selected result, nothing if there is none.
+    fn execute(&self, command: &str) -> Option<Action> {
+        let item = self.selected_item()?;
+        let command = expand_placeholders(
+            command,
+            &item,
+            &self.marked_items(),
+            self.search_input.text(),
+        );
+        Some(Action::Execute(command))
+    }
+
+    /// Replace the candidates by the output of `command` and search them.
+    fn reload(&mut self, command: &str) -> Result<()> {
+        let item = self.selected_item().unwrap_or_default();
+        let command = expand_placeholders(
+            command,
+            &item,
+            &self.marked_items(),
+            self.search_input.text(),
+        );
+        // stop the command still read from before starting the next one
+        self.process = None;
+        let items = Arc::new(ItemStore::loading());
+        self.process = Source::Command(command).spawn_reader(items.clone())?;
+        // the old worker thread ends once it is dropped
+        self.worker = MatchWorker::start(items.clone(), self.ranking.clone(), self.fields.clone());
+        self.items = items;
+        self.marked.clear();
+        // the results index the old items
+        self.result_list = Arc::default();
+        self.result_state.select(None);
+        self.submit_search();
         Ok(())
     }
 
-    fn draw(&self, frame: &mut Frame) {
-        let [title, input_area, result_area] = Layout::vertical([
-            Constraint::Length(1),
+    /// Run a bound action, editing actions change the query.
+    fn perform(&mut self, action: KeyAction) -> Result<Option<Action>> {
+        let input = &mut self.search_input;
+        match action {
+            KeyAction::Accept => return Ok(Some(self.accept())),
+            KeyAction::Abort => return Ok(Some(Action::Quit)),
+            KeyAction::Execute(command) => return Ok(self.execute(&command)),
+            KeyAction::Reload(command) => self.reload(&command)?,
+            KeyAction::Up => self.move_selection(-1),
+            KeyAction::Down => self.move_selection(1),
+            KeyAction::PageUp => self.move_selection(-self.page_offset()),
+            KeyAction::PageDown => self.move_selection(self.page_offset()),
+            KeyAction::First => self.move_selection(isize::MIN),
+            KeyAction::Last => self.move_selection(isize::MAX),
+            KeyAction::ToggleDown => self.toggle_mark(1),
+            KeyAction::ToggleUp => self.toggle_mark(-1),
+            KeyAction::TogglePreview => self.toggle_preview(),
+            KeyAction::PreviewUp => self.scroll_preview(-1),
+            KeyAction::PreviewDown => self.scroll_preview(1),
+            KeyAction::NextMatcher => self.next_matcher(),
+            KeyAction::ToggleScores => self.show_scores = !self.show_scores,
+            KeyAction::HistoryPrev => {
+                let query = self.history.recall_older(self.search_input.text());
+                self.recall(query);
+            }
+            KeyAction::HistoryNext => {
+                let query = self.history.recall_newer();
+                self.recall(query);
+            }
+            KeyAction::HistorySearch => {
+                let query = self.history.search_backward(self.search_input.text());
+                self.recall(query);
+            }
+            KeyAction::ClearQuery => input.set_text(""),
+            KeyAction::BackwardChar => input.move_cursor_left(),
+            KeyAction::ForwardChar => input.move_cursor_right(),
+            KeyAction::BeginningOfLine => input.move_cursor_start(),
+            KeyAction::EndOfLine => input.move_cursor_end(),
+            KeyAction::BackwardWord => input.move_word_left(),
+            KeyAction::ForwardWord => input.move_word_right(),
+            KeyAction::BackwardDeleteChar => input.delete_char(),
+            KeyAction::DeleteChar => input.delete_char_forward(),
+            KeyAction::BackwardKillWord => input.delete_word_before(),
+            KeyAction::UnixLineDiscard => input.kill_to_start(),
+            KeyAction::KillLine => input.kill_to_end(),
+            KeyAction::Yank => input.yank(),
+            KeyAction::Undo => input.undo(),
+            KeyAction::Redo => input.redo(),
+            KeyAction::Ignore => {}
+        }
+        Ok(None)
+    }
+
+    /// Row of the result list at `position`.
+    fn result_at(&self, position: Position) -> Option<usize> {
+        let list = inner(self.result_area);
+        if !list.contains(position) {
+            return None;
+        }
+        let row = self.result_state.offset() + usize::from(position.y - list.y);
+        (row < self.result_list.len()).then_some(row)
+    }
+}
+
+impl Component for App {
+    /// Items are still read or the current query is not matched yet.
+    fn is_busy(&self) -> bool {
+        self.items.is_loading() || self.result_generation != self.search_generation
+    }
+
+    fn handle_event(&mut self, event: &AppEvent) -> Result<Option<Action>> {
+        if let AppEvent::Mouse(mouse) = event {
+            return Ok(self.handle_mouse(mouse));
+        }
+        if let AppEvent::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(None);
+            }
+            let query = self.search_input.text().to_string();
+            let bound = self.keymap.get(key).cloned();
+            // recalling sets the query too, but must keep its place in the history
+            let recalled = matches!(
+                bound,
+                Some(KeyAction::HistoryPrev | KeyAction::HistoryNext | KeyAction::HistorySearch)
+            );
+            let action = match bound {
+                Some(action) => self.perform(action)?,
+                None => {
+                    // unbound chars are typed, with or without shift
+                    if let KeyCode::Char(c) = key.code {
+                        if key.modifiers.difference(KeyModifiers::SHIFT).is_empty() {
+                            self.search_input.enter_char(c);
+                        }
+                    }
+                    None
+                }
+            };
+            if !recalled && self.search_input.text() != query {
+                self.history.reset();
+                self.submit_search();
+            }
+            return Ok(action);
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick {
+            let received = self.receive_results();
+            let busy = self.is_busy();
+            if busy {
+                self.spinner = self.spinner.wrapping_add(1);
+            }
+            if self.update_preview() || received || busy {
+                return Ok(Some(Action::Render));
+            }
+        }
+        Ok(None)
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        // low inline viewports leave out the top row and the spacing
+        let compact = area.height < COMPACT_HEIGHT;
+        let [_title, input_area, result_area, status_area] = Layout::vertical([
+            Constraint::Length(if compact { 0 } else { 1 }),
             Constraint::Length(3),
             Constraint::Min(1),
+            Constraint::Length(1),
         ])
-        .spacing(1)
-        .areas(frame.area());
-        let search_input = Paragraph::new(self.search_input.as_str())
-            .style(Style::default())
-            .block(Block::bordered().title("Input"));
+        .spacing(if compact { 0 } else { 1 })
+        .areas(area);
+        let (result_area, preview_area) = self.split_preview(result_area);
+        let theme = self.theme;
+        frame.buffer_mut().set_style(area, theme.text);
+        // scroll the input horizontally to keep the cursor inside the border
+        let cursor_column = self.search_input.cursor_column() as u16;
+        let input_scroll = cursor_column.saturating_sub(input_area.width.saturating_sub(3));
+        self.input_scroll = input_scroll;
+        self.input_area = input_area;
+        self.result_area = result_area;
+        self.preview_area = preview_area;
+        let search_input = Paragraph::new(self.search_input.text())
+            .style(theme.prompt)
+            .scroll((0, input_scroll))
+            .block(
+                self.block(format!("Input [{}]", self.matcher_kind))
+                    .title_bottom(self.counter().right_aligned()),
+            );
         frame.render_widget(search_input, input_area);
         frame.set_cursor_position(Position::new(
-            input_area.x + self.search_input_character_index as u16 + 1,
+            input_area.x + cursor_column - input_scroll + 1,
             input_area.y + 1,
         ));
-        let book_list_filtered: Vec<ListItem> = self
-            .result_list
+        // inside the border
+        self.result_page_size = result_area.height.saturating_sub(2) as usize;
+        let visible = self.visible_results();
+        let items = self.items.snapshot();
+        // columns left of the border, the highlight symbol and the marker
+        let text_width = usize::from(result_area.width)
+            .saturating_sub(2 + HIGHLIGHT_SYMBOL.len() + if self.multi { 2 } else { 0 });
+        let book_list_filtered: Vec<ListItem> = self.result_list[visible.clone()]
             .iter()
-            .enumerate()
-            .map(|(i, m)| {
-                // let content = Line::from(Span::raw(format!("{i}: {} {}", m.0, m.2,)));
-                let content = highlight_char_in_text(&m.2, &m.1);
+            .map(|m| {
+                let item = items.get(m.2).unwrap_or_default();
+                let display = self.fields.display(item);
+                let indices = self.fields.display_indices(item, &m.1);
+                let score_column = self.show_scores.then(|| self.score_column(m));
+                let score_width = score_column.as_ref().map_or(0, |s| s.chars().count());
+                let mut content = Highlight::new(&display, &indices)
+                    .highlight_style(theme.matched)
+                    .width(text_width.saturating_sub(score_width))
+                    .into_line();
+                if let Some(score_column) = score_column {
+                    content
+                        .spans
+                        .insert(0, Span::styled(score_column, theme.info));
+                }
+                if self.multi {
+                    let marker = if self.marked.contains(&m.2) {
+                        "+ "
+                    } else {
+                        "  "
+                    };
+                    content.spans.insert(0, Span::styled(marker, theme.marker));
+                }
                 ListItem::new(content)
             })
             .collect();
-        let book_list_filtered =
-            List::new(book_list_filtered).block(Block::bordered().title("search results"));
-        frame.render_widget(book_list_filtered, result_area);
+        let results_title = if self.multi {
+            format!("search results ({} selected)", self.marked.len())
+        } else {
+            "search results".to_string()
+        };
+        let book_list_filtered = List::new(book_list_filtered)
+            .block(self.block(results_title))
+            .highlight_style(theme.selected)
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
+        // only the visible rows are built, the state is relative to them
+        let selected = self.result_state.selected().map(|i| i - visible.start);
+        let mut visible_state = ListState::default().with_selected(selected);
+        frame.render_stateful_widget(book_list_filtered, result_area, &mut visible_state);
+        if let Some(preview_area) = preview_area {
+            self.render_preview(frame, preview_area);
+        }
+        frame.render_widget(self.status_line(), status_area);
+    }
+}
+
+/// in front of the selected result
+const HIGHLIGHT_SYMBOL: &str = "> ";
+
+/// second click on the same result within this time accepts it
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+
+/// Area inside a border.
+fn inner(area: Rect) -> Rect {
+    area.inner(Margin::new(1, 1))
+}
+
+/// below this height the layout has no top row and spacing
+const COMPACT_HEIGHT: u16 = 16;
+
+/// matched positions shown in the score column, longer lists are cut off
+const SCORE_POSITIONS_WIDTH: usize = 16;
+
+/// braille spinner shown while loading or searching
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+impl App {
+    /// `matched/total`, with a spinner while loading or searching.
+    fn counter(&self) -> Line<'static> {
+        let spinner = if self.is_busy() {
+            SPINNER[self.spinner % SPINNER.len()]
+        } else {
+            " "
+        };
+        Line::styled(
+            format!(
+                " {spinner} {}/{} ",
+                self.result_list.len(),
+                self.items.len()
+            ),
+            self.theme.info,
+        )
+    }
+
+    /// `score positions matcher` in front of a result, shown with F2.
+    fn score_column(&self, result: &MatchResult) -> String {
+        let positions: Vec<String> = result.1.iter().map(usize::to_string).collect();
+        let mut positions = positions.join(",");
+        if positions.chars().count() > SCORE_POSITIONS_WIDTH {
+            positions = positions
+                .chars()
+                .take(SCORE_POSITIONS_WIDTH - 1)
+                .chain(['…'])
+                .collect();
+        }
+        format!(
+            "{:>6} {positions:<width$} {:<6} ",
+            result.0,
+            self.matcher_kind,
+            width = SCORE_POSITIONS_WIDTH
+        )
+    }
+
+    /// Candidate and match counts with the search latency.
+    fn status_line(&self) -> Line<'static> {
+        let latency = if self.is_busy() {
+            "searching".to_string()
+        } else {
+            format!("{} ms", self.search_duration.as_millis())
+        };
+        Line::styled(
+            format!(
+                "{} candidates  {} matched  {latency}",
+                self.items.len(),
+                self.result_list.len()
+            ),
+            self.theme.info,
+        )
+    }
+
+    /// Bordered block in the colors of the theme.
+    fn block<'a>(&self, title: impl Into<Line<'a>>) -> Block<'a> {
+        Block::bordered()
+            .border_style(self.theme.border)
+            .title(title)
+            .title_style(self.theme.label)
+    }
+
+    /// Range of results shown in the list, scrolled to keep the selection visible.
+    fn visible_results(&mut self) -> Range<usize> {
+        let page_size = self.result_page_size.max(1);
+        let mut offset = self.result_state.offset();
+        if let Some(selected) = self.result_state.selected() {
+            offset = offset.clamp(selected.saturating_sub(page_size - 1), selected);
+        }
+        let offset = offset.min(self.result_list.len());
+        *self.result_state.offset_mut() = offset;
+        offset..(offset + page_size).min(self.result_list.len())
+    }
+
+    /// Areas of the result list and, if shown, the preview pane.
+    fn split_preview(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if !self.preview_visible {
+            return (area, None);
+        }
+        let halves = [Constraint::Percentage(50), Constraint::Percentage(50)];
+        let [result_area, preview_area] = match self.preview_position {
+            PreviewPosition::Right => Layout::horizontal(halves).areas(area),
+            PreviewPosition::Bottom => Layout::vertical(halves).areas(area),
+        };
+        (result_area, Some(preview_area))
+    }
+
+    fn render_preview(&mut self, frame: &mut Frame, area: Rect) {
+        let preview = self
+            .selected_item()
+            .and_then(|item| self.previewer.get(&item))
+            .unwrap_or_default();
+        self.preview_scroll = self.preview_scroll.min(preview.len().saturating_sub(1));
+        let lines: Vec<Line> = preview
+            .iter()
+            .skip(self.preview_scroll)
+            .map(|line| Line::raw(line.as_str()))
+            .collect();
+        let title = match preview.len() {
+            0 => "preview".to_string(),
+            len => format!("preview {}/{len}", self.preview_scroll + 1),
+        };
+        let preview = Paragraph::new(lines)
+            .style(self.theme.preview)
+            .block(self.block(title));
+        frame.render_widget(preview, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEvent;
+    use ratatui_examples::{finder::keymap::parse_bindings, script::Headless};
+
+    fn app(items: &[&str], args: &[&str]) -> App {
+        let options = Options::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+        let items = ItemStore::from_items(items.iter().map(|item| item.to_string()).collect());
+        let mut app = App::new(
+            Arc::new(items),
+            &options,
+            History::in_memory(10),
+            None,
+            Config::default(),
+        );
+        settle(&mut app);
+        app
+    }
+
+    /// Tick until the worker delivered the results of the current query.
+    fn settle(app: &mut App) {
+        let started = Instant::now();
+        while app.is_busy() {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "search timed out"
+            );
+            app.update(Action::Tick).unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn press(app: &mut App, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        let action = app
+            .handle_event(&AppEvent::Key(KeyEvent::new(code, modifiers)))
+            .unwrap();
+        settle(app);
+        action
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+    }
+
+    fn results(app: &App) -> Vec<String> {
+        app.result_list
+            .iter()
+            .filter_map(|(_, _, index)| app.items.get(*index))
+            .collect()
+    }
+
+    const BOOKS: [&str; 4] = ["Dune", "Neuromancer", "Snow Crash", "Hyperion"];
+
+    #[test]
+    fn typing_filters_the_results() {
+        let mut app = app(&BOOKS, &[]);
+        assert_eq!(results(&app).len(), 4);
+        type_text(&mut app, "euro");
+        assert_eq!(results(&app), ["Neuromancer"]);
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        }
+        assert_eq!(results(&app), ["Dune", "Neuromancer", "Hyperion"]);
+    }
+
+    #[test]
+    fn enter_accepts_the_selected_result() {
+        let mut app = app(&BOOKS, &[]);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        let action = press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(action, Some(Action::Quit));
+        assert_eq!(app.selection, Some(vec!["Neuromancer".to_string()]));
+    }
+
+    #[test]
+    fn esc_aborts_without_selection() {
+        let mut app = app(&BOOKS, &[]);
+        let action = press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(action, Some(Action::Quit));
+        assert_eq!(app.selection, None);
+    }
+
+    #[test]
+    fn tab_marks_several_results() {
+        let mut app = app(&BOOKS, &["--multi"]);
+        press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        let selection = app.selection.unwrap();
+        assert_eq!(selection, ["Dune", "Snow Crash"]);
+    }
+
+    #[test]
+    fn bind_overrides_default_keys() {
+        let mut app = app(&BOOKS, &[]);
+        for (chord, action) in parse_bindings("ctrl-j:down,enter:ignore").unwrap() {
+            app.keymap.bind(chord, action);
+        }
+        press(&mut app, KeyCode::Char('j'), KeyModifiers::CONTROL);
+        assert_eq!(app.selected_item().as_deref(), Some("Neuromancer"));
+        assert_eq!(press(&mut app, KeyCode::Enter, KeyModifiers::NONE), None);
+    }
+
+    #[test]
+    fn execute_expands_placeholders() {
+        let mut app = app(&BOOKS, &[]);
+        app.keymap.bind(
+            "ctrl-o".parse().unwrap(),
+            KeyAction::Execute("echo {} {q}".to_string()),
+        );
+        type_text(&mut app, "hyp");
+        let action = press(&mut app, KeyCode::Char('o'), KeyModifiers::CONTROL);
+        assert_eq!(
+            action,
+            Some(Action::Execute("echo 'Hyperion' 'hyp'".to_string()))
+        );
+    }
+
+    #[test]
+    fn alt_up_and_down_step_through_the_history() {
+        let mut app = app(&BOOKS, &[]);
+        for query in ["dune", "hyp", "neuro"] {
+            app.history.add(query);
+        }
+        type_text(&mut app, "sn");
+        let mut recall = |code| {
+            press(&mut app, code, KeyModifiers::ALT);
+            app.search_input.text().to_string()
+        };
+        let older: Vec<_> = (0..4).map(|_| recall(KeyCode::Up)).collect();
+        assert_eq!(older, ["neuro", "hyp", "dune", "dune"]);
+        let newer: Vec<_> = (0..3).map(|_| recall(KeyCode::Down)).collect();
+        assert_eq!(newer, ["hyp", "neuro", "sn"]);
+    }
+
+    #[test]
+    fn render_snapshot() {
+        let mut app = app(&BOOKS, &[]);
+        type_text(&mut app, "n");
+        // the latency in the status line is the only thing varying between runs
+        app.search_duration = Duration::ZERO;
+        let screen = Headless::new(30, 11).unwrap().screen(&mut app).unwrap();
+        assert_eq!(screen.lines(), SNAPSHOT);
+        // the matched `N` of the selected row is highlighted
+        assert_eq!(screen.buffer[(3, 4)].fg, ratatui::style::Color::Green);
+        assert_eq!(screen.buffer[(4, 4)].fg, ratatui::style::Color::Reset);
+    }
+
+    const SNAPSHOT: [&str; 11] = [
+        "┌Input [skim]────────────────┐",
+        "│n                           │",
+        "└─────────────────────   4/4 ┘",
+        "┌search results──────────────┐",
+        "│> Neuromancer               │",
+        "│  Dune                      │",
+        "│  Snow Crash                │",
+        "│  Hyperion                  │",
+        "│                            │",
+        "└────────────────────────────┘",
+        "4 candidates  4 matched  0 ms ",
+    ];
+
+    #[test]
+    fn script_drives_the_app() {
+        let script = "resize 30 11\ntype n\nkey down\nkey enter\nkey up"
+            .parse()
+            .unwrap();
+        let mut app = app(&BOOKS, &[]);
+        let snapshots = Headless::new(80, 24)
+            .unwrap()
+            .run(&script, &mut app)
+            .unwrap();
+        let steps: Vec<_> = snapshots.iter().map(|s| s.step.as_str()).collect();
+        assert_eq!(
+            steps,
+            ["start", "resize 30 11", "type n", "key down", "key enter"]
+        );
+        assert_eq!(snapshots[2].lines()[4], "│> Neuromancer               │");
+        assert_eq!(snapshots[3].lines()[5], "│> Dune                      │");
+        assert_eq!(app.selection, Some(vec!["Dune".to_string()]));
+    }
+
+    #[test]
+    fn render_at_several_sizes() {
+        let mut app = app(&BOOKS, &["--preview-window", "right"]);
+        Headless::new(1, 1).unwrap().screen(&mut app).unwrap();
+        for (width, height) in [(20, 8), (40, 12), (80, 24), (120, 40)] {
+            let lines = Headless::new(width, height)
+                .unwrap()
+                .screen(&mut app)
+                .unwrap()
+                .lines();
+            assert!(
+                lines.iter().any(|line| line.contains("Dune")),
+                "{width}x{height}: {lines:#?}"
+            );
+            // the input moves up to the first row in the compact layout
+            let compact = lines[0].contains("Input");
+            assert_eq!(compact, height < COMPACT_HEIGHT);
+        }
     }
 }
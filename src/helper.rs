@@ -1,28 +1,353 @@
 use ratatui::{
-    style::{Color, Style, Stylize},
-    text::{Line, Span, Text},
+    style::Style,
+    text::{Line, Span},
 };
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-pub fn highlight_char_in_text<'a>(text: &'a str, indices: &'a [usize]) -> Line<'a> {
-    let mut span_vec = Vec::new();
-    let mut last_string = Vec::new();
-    for (idx, itm) in text.chars().enumerate() {
-        if indices.contains(&idx) {
-            if !last_string.is_empty() {
-                span_vec.push(Span::from(last_string.into_iter().collect::<String>()));
+const ELLIPSIS: &str = "…";
+
+/// What the match indices passed to [`Highlight`] count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexMode {
+    /// `char` positions, as reported by the fuzzy matchers
+    #[default]
+    Char,
+    /// byte offsets into the UTF-8 text, as reported by `regex`
+    Byte,
+}
+
+/// A line with its matched positions highlighted.
+///
+/// Indices have to be sorted. A grapheme cluster is highlighted as a whole
+/// if any of its chars is matched, so `e` plus a combining accent never gets
+/// split into two differently styled spans. Neighbouring graphemes with the
+/// same style end up in one span.
+#[derive(Debug, Clone)]
+pub struct Highlight<'a> {
+    text: &'a str,
+    indices: &'a [usize],
+    mode: IndexMode,
+    style: Style,
+    highlight_style: Style,
+    width: Option<usize>,
+}
+
+impl<'a> Highlight<'a> {
+    pub fn new(text: &'a str, indices: &'a [usize]) -> Self {
+        Self {
+            text,
+            indices,
+            mode: IndexMode::default(),
+            style: Style::default(),
+            highlight_style: Style::default(),
+            width: None,
+        }
+    }
+
+    pub fn mode(mut self, mode: IndexMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// style of the unmatched text
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// style of the matched text
+    pub fn highlight_style(mut self, highlight_style: Style) -> Self {
+        self.highlight_style = highlight_style;
+        self
+    }
+
+    /// Columns available, longer lines are cut off with `…` on one or both
+    /// sides so the first match stays visible.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn into_line(self) -> Line<'static> {
+        let graphemes = self.graphemes();
+        let visible = match self.width {
+            Some(width) => visible_range(&graphemes, width),
+            None => Visible {
+                range: 0..graphemes.len(),
+                left_ellipsis: false,
+                right_ellipsis: false,
+            },
+        };
+        let mut spans = Vec::new();
+        if visible.left_ellipsis {
+            spans.push(Span::styled(ELLIPSIS, self.style));
+        }
+        let mut run = String::new();
+        let mut run_highlighted = false;
+        for grapheme in &graphemes[visible.range] {
+            if grapheme.highlighted != run_highlighted && !run.is_empty() {
+                spans.push(self.span(std::mem::take(&mut run), run_highlighted));
             }
-            last_string = Vec::new();
-            let highlight_span = Span::styled(
-                format!("{}", itm.clone()),
-                Style::default().fg(Color::Green),
-            );
-            span_vec.push(highlight_span);
+            run_highlighted = grapheme.highlighted;
+            run.push_str(grapheme.text);
+        }
+        if !run.is_empty() {
+            spans.push(self.span(run, run_highlighted));
+        }
+        if visible.right_ellipsis {
+            spans.push(Span::styled(ELLIPSIS, self.style));
+        }
+        Line::from(spans)
+    }
+
+    fn span(&self, text: String, highlighted: bool) -> Span<'static> {
+        let style = if highlighted {
+            self.highlight_style
         } else {
-            last_string.push(itm.clone());
+            self.style
+        };
+        Span::styled(text, style)
+    }
+
+    /// Graphemes with their width and whether one of their positions matched.
+    fn graphemes(&self) -> Vec<Grapheme<'a>> {
+        let mut indices = self.indices.iter().copied().peekable();
+        let mut char_index = 0;
+        self.text
+            .grapheme_indices(true)
+            .map(|(byte_index, text)| {
+                let positions = match self.mode {
+                    IndexMode::Char => char_index..char_index + text.chars().count(),
+                    IndexMode::Byte => byte_index..byte_index + text.len(),
+                };
+                char_index += text.chars().count();
+                while indices.next_if(|&i| i < positions.start).is_some() {}
+                let highlighted = indices.peek().is_some_and(|&i| i < positions.end);
+                Grapheme {
+                    text,
+                    width: text.width(),
+                    highlighted,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Highlight the chars at `indices` of `text`, which have to be sorted.
+pub fn highlight_char_in_text(text: &str, indices: &[usize], style: Style) -> Line<'static> {
+    Highlight::new(text, indices)
+        .highlight_style(style)
+        .into_line()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Grapheme<'a> {
+    text: &'a str,
+    width: usize,
+    highlighted: bool,
+}
+
+/// Graphemes shown of a line cut to a width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Visible {
+    range: std::ops::Range<usize>,
+    left_ellipsis: bool,
+    right_ellipsis: bool,
+}
+
+/// Cut the line to `width` columns, keeping the first match visible with
+/// about a third of the space as context in front of it.
+fn visible_range(graphemes: &[Grapheme], width: usize) -> Visible {
+    let total: usize = graphemes.iter().map(|g| g.width).sum();
+    if total <= width {
+        return Visible {
+            range: 0..graphemes.len(),
+            left_ellipsis: false,
+            right_ellipsis: false,
+        };
+    }
+    // columns of graphemes from `start` on, fitting into `space`
+    let fit = |start: usize, space: usize| {
+        let mut used = 0;
+        let mut end = start;
+        while let Some(grapheme) = graphemes.get(end) {
+            if used + grapheme.width > space {
+                break;
+            }
+            used += grapheme.width;
+            end += 1;
+        }
+        end
+    };
+    let first_match = graphemes.iter().position(|g| g.highlighted).unwrap_or(0);
+    let space = width.saturating_sub(ELLIPSIS.width());
+    // too narrow for text between two ellipses
+    if fit(0, space) > first_match || width <= 2 * ELLIPSIS.width() {
+        return Visible {
+            range: 0..fit(0, space),
+            left_ellipsis: false,
+            right_ellipsis: width > 0,
+        };
+    }
+    // both ellipses, the match a third into the remaining space
+    let space = width.saturating_sub(2 * ELLIPSIS.width());
+    let mut start = first_match;
+    let mut context = 0;
+    while start > 0 && context + graphemes[start - 1].width <= space / 3 {
+        start -= 1;
+        context += graphemes[start].width;
+    }
+    let rest: usize = graphemes[start..].iter().map(|g| g.width).sum();
+    let space_without_right = width.saturating_sub(ELLIPSIS.width());
+    if rest <= space_without_right {
+        // the end fits, use the space left for more context
+        let mut used = rest;
+        while start > 0 && used + graphemes[start - 1].width <= space_without_right {
+            start -= 1;
+            used += graphemes[start].width;
         }
+        return Visible {
+            range: start..graphemes.len(),
+            left_ellipsis: true,
+            right_ellipsis: false,
+        };
     }
-    if !last_string.is_empty() {
-        span_vec.push(Span::from(last_string.into_iter().collect::<String>()));
+    Visible {
+        range: start..fit(start, space),
+        left_ellipsis: true,
+        right_ellipsis: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Stylize;
+
+    fn spans(line: &Line) -> Vec<(String, Style)> {
+        line.spans
+            .iter()
+            .map(|span| (span.content.to_string(), span.style))
+            .collect()
+    }
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn highlight_char_in_text_merges_runs() {
+        let green = Style::new().green();
+        let line = highlight_char_in_text("foobar", &[0, 1, 4], green);
+        assert_eq!(
+            spans(&line),
+            [
+                ("fo".to_string(), green),
+                ("ob".to_string(), Style::new()),
+                ("a".to_string(), green),
+                ("r".to_string(), Style::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlight_without_matches_is_one_span() {
+        let line = highlight_char_in_text("foobar", &[], Style::new().green());
+        assert_eq!(spans(&line), [("foobar".to_string(), Style::new())]);
+    }
+
+    #[test]
+    fn base_style_applies_to_unmatched_text() {
+        let line = Highlight::new("ab", &[1])
+            .style(Style::new().dim())
+            .highlight_style(Style::new().bold())
+            .into_line();
+        assert_eq!(
+            spans(&line),
+            [
+                ("a".to_string(), Style::new().dim()),
+                ("b".to_string(), Style::new().bold()),
+            ]
+        );
+    }
+
+    #[test]
+    fn char_and_byte_indices() {
+        let bold = Style::new().bold();
+        let by_char = Highlight::new("äbc", &[1])
+            .highlight_style(bold)
+            .into_line();
+        // `ä` takes two bytes, byte 2 is `b` as well
+        let by_byte = Highlight::new("äbc", &[2])
+            .mode(IndexMode::Byte)
+            .highlight_style(bold)
+            .into_line();
+        assert_eq!(spans(&by_char), spans(&by_byte));
+        assert_eq!(spans(&by_char)[1], ("b".to_string(), bold));
+    }
+
+    #[test]
+    fn combining_chars_are_highlighted_with_their_base() {
+        let bold = Style::new().bold();
+        // `e` and the combining acute accent are chars 1 and 2
+        let line = Highlight::new("ce\u{301}x", &[1])
+            .highlight_style(bold)
+            .into_line();
+        assert_eq!(spans(&line)[1], ("e\u{301}".to_string(), bold));
+        let line = Highlight::new("ce\u{301}x", &[2])
+            .highlight_style(bold)
+            .into_line();
+        assert_eq!(spans(&line)[1], ("e\u{301}".to_string(), bold));
+    }
+
+    #[test]
+    fn short_lines_are_not_truncated() {
+        let line = Highlight::new("abc", &[2]).width(3).into_line();
+        assert_eq!(text(&line), "abc");
+    }
+
+    #[test]
+    fn truncate_right_if_first_match_fits() {
+        let line = Highlight::new("abcdefghij", &[1]).width(5).into_line();
+        assert_eq!(text(&line), "abcd…");
+    }
+
+    #[test]
+    fn truncate_left_to_show_match_at_end() {
+        let line = Highlight::new("abcdefghij", &[9]).width(5).into_line();
+        assert_eq!(text(&line), "…ghij");
+    }
+
+    #[test]
+    fn truncate_both_sides_around_match() {
+        let bold = Style::new().bold();
+        let text_line = "a".repeat(20) + "needle" + &"b".repeat(20);
+        let indices: Vec<usize> = (20..26).collect();
+        let line = Highlight::new(&text_line, &indices)
+            .highlight_style(bold)
+            .width(14)
+            .into_line();
+        assert_eq!(text(&line), "…aaaaneedlebb…");
+        assert!(spans(&line).contains(&("needle".to_string(), bold)));
+    }
+
+    #[test]
+    fn truncate_counts_wide_chars() {
+        let line = Highlight::new("日本語テキスト", &[0]).width(7).into_line();
+        assert_eq!(text(&line), "日本語…");
+    }
+
+    #[test]
+    fn truncate_to_tiny_widths() {
+        assert_eq!(text(&Highlight::new("abc", &[2]).width(0).into_line()), "");
+        assert_eq!(text(&Highlight::new("abc", &[2]).width(1).into_line()), "…");
+        assert_eq!(
+            text(&Highlight::new("abc", &[2]).width(2).into_line()),
+            "a…"
+        );
     }
-    span_vec.into()
 }
@@ -0,0 +1,169 @@
+use color_eyre::{eyre::bail, Report, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{fmt, str::FromStr};
+
+/// A key with modifiers like `ctrl-c`, `alt-enter` or `shift-up`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // shift is part of uppercase chars and of BackTab already
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers.difference(KeyModifiers::SHIFT),
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = s;
+        // a single char is a key even if it is `-`
+        while key.chars().count() > 1 {
+            if let Some(rest) = key.strip_prefix("ctrl-") {
+                modifiers |= KeyModifiers::CONTROL;
+                key = rest;
+            } else if let Some(rest) = key.strip_prefix("alt-") {
+                modifiers |= KeyModifiers::ALT;
+                key = rest;
+            } else if let Some(rest) = key.strip_prefix("shift-") {
+                modifiers |= KeyModifiers::SHIFT;
+                key = rest;
+            } else {
+                break;
+            }
+        }
+        let code = match key {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "btab" => KeyCode::BackTab,
+            "bspace" | "backspace" => KeyCode::Backspace,
+            "del" | "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "page-up" | "pgup" => KeyCode::PageUp,
+            "page-down" | "pgdn" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            _ => match key.strip_prefix('f').map(str::parse) {
+                Some(Ok(n @ 1..=12)) => KeyCode::F(n),
+                _ => {
+                    let mut chars = key.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                            // the shifted key of anything but a letter depends on the layout
+                            let mut upper = c.to_uppercase();
+                            match (upper.next(), upper.next()) {
+                                (Some(upper), None) if upper != c => KeyCode::Char(upper),
+                                _ => bail!("no shifted key for {s}, bind the char itself"),
+                            }
+                        }
+                        (Some(c), None) => KeyCode::Char(c),
+                        _ => bail!("unknown key {s}"),
+                    }
+                }
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, prefix) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(prefix)?;
+            }
+        }
+        match self.code {
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::BackTab => f.write_str("btab"),
+            KeyCode::Backspace => f.write_str("bspace"),
+            KeyCode::Delete => f.write_str("del"),
+            KeyCode::Insert => f.write_str("insert"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::PageUp => f.write_str("page-up"),
+            KeyCode::PageDown => f.write_str("page-down"),
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_chords() {
+        let chord = |s: &str| s.parse::<KeyChord>().unwrap();
+        assert_eq!(
+            chord("ctrl-alt-x"),
+            KeyChord::new(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        assert_eq!(chord("shift-tab"), chord("btab"));
+        assert_eq!(
+            chord("f12"),
+            KeyChord::new(KeyCode::F(12), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            chord("-"),
+            KeyChord::new(KeyCode::Char('-'), KeyModifiers::NONE)
+        );
+        assert!("ctrl-".parse::<KeyChord>().is_err());
+        assert!("f13".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn shift_chars_parse_as_uppercase() {
+        let chord = |s: &str| s.parse::<KeyChord>().unwrap();
+        assert_eq!(chord("shift-a"), chord("A"));
+        assert_eq!(chord("ctrl-shift-ä"), chord("ctrl-Ä"));
+        assert_ne!(chord("shift-a"), chord("a"));
+        assert!("shift-1".parse::<KeyChord>().is_err());
+        assert!("shift-A".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn chords_display_as_parsed() {
+        for chord in ["ctrl-alt-x", "btab", "f12", "space", "-", "alt-page-down"] {
+            assert_eq!(chord.parse::<KeyChord>().unwrap().to_string(), chord);
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Shared building blocks for the ratatui examples.
+
+pub mod action;
+pub mod component;
+pub mod event;
+pub mod finder;
+pub mod helper;
+pub mod key;
+pub mod runtime;
+pub mod script;
+pub mod tui;
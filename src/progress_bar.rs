@@ -1,66 +1,53 @@
-use color_eyre::owo_colors::OwoColorize;
 use color_eyre::Result;
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
+use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::{
     layout::{Constraint, Layout},
-    prelude::{Buffer, CrosstermBackend, Rect, Terminal},
+    prelude::{Buffer, Rect},
     style::{Color, Style, Stylize},
     symbols::border,
     text::Line,
     widgets::{Block, Gauge, Widget},
-    DefaultTerminal, Frame,
+    Frame,
 };
-use std::{
-    io::{self, stdout, Stdout},
-    thread::{self, sleep},
+use ratatui_examples::{
+    action::Action,
+    component::Component,
+    event::AppEvent,
+    runtime::Runtime,
+    script::{run_script, Script},
+    tui::{install_hooks, Tui, TuiOptions},
 };
+use std::{env::args, thread};
 mod dummy_thread;
 use dummy_thread::Dummy;
 
 fn main() -> Result<()> {
-    color_eyre::install()?;
-    //    let terminal = ratatui::init();
-    let mut terminal = init_tui()?;
+    install_hooks()?;
+    let mut args: Vec<String> = args().skip(1).collect();
+    let script = Script::take_arg(&mut args)?;
+    let options = TuiOptions::parse(args)?;
+    if let Some(script) = script {
+        return run_script(&script, &mut App::scripted());
+    }
     let (thread_join_handle, tx_close, rx_status) = Dummy::start();
     let mut app: App = App::new(thread_join_handle, tx_close, rx_status);
-    let app_result = app.run(&mut terminal);
-    restore_tui()?;
+    let mut tui = Tui::with_options(options)?;
+    let app_result = Runtime::new().tick_rate(20.0).run(&mut tui, &mut app);
+    tui.restore()?;
     app_result
 }
 
-pub fn init_tui() -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
-    stdout().execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    Terminal::new(CrosstermBackend::new(stdout()))
-}
-
-pub fn restore_tui() -> io::Result<()> {
-    stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
-    Ok(())
-}
-
-enum KeyMsg {
-    Close,
-    ChangeColor,
-}
-
 pub struct App {
-    should_exit: bool,
     progress_bar_color_idx: u8,
     progress_name: String,
     progress_ratio: f64,
     progress_thread_jh: Option<thread::JoinHandle<()>>,
-    input_thread_jh: Option<thread::JoinHandle<()>>,
     tx_close: Sender<bool>,
-    tx_close_input: Sender<bool>,
     rx_progress: Receiver<f64>,
-    rx_keymsg: Receiver<KeyMsg>,
+    /// `--script`: progress in percent, advanced by every tick instead of
+    /// the dummy thread, so the screens don't depend on timing
+    ticked_progress: Option<f64>,
 }
 
 impl App {
@@ -69,81 +56,77 @@ impl App {
         tx_close: Sender<bool>,
         rx_progress: Receiver<f64>,
     ) -> Self {
-        let (tx_close_input, rx_close_input) = unbounded::<bool>();
-        let (tx_keymsg, rx_keymsg) = unbounded();
-        let input_thread_jh = std::thread::spawn(move || {
-            let mut run = true;
-
-            while run {
-                if let Event::Key(key) = event::read().expect("read sth from input") {
-                    if key.kind == KeyEventKind::Press
-                        && (key.code == KeyCode::Char('q')
-                            || key.code == KeyCode::Char('c')
-                                && key.modifiers == KeyModifiers::CONTROL)
-                    {
-                        let _ = tx_keymsg.send(KeyMsg::Close);
-                    } else if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('c') {
-                        let _ = tx_keymsg.send(KeyMsg::ChangeColor);
-                    }
-                }
-                match rx_close_input.try_recv() {
-                    Ok(close) => {
-                        run = !close;
-                    }
-                    Err(_) => {}
-                }
-            }
-        });
         Self {
-            should_exit: false,
             progress_bar_color_idx: 0,
             progress_name: "Process 1".to_string(),
             progress_ratio: 0.0,
             progress_thread_jh: Some(progress_thread_jh),
             tx_close,
-            tx_close_input,
             rx_progress,
-            input_thread_jh: Some(input_thread_jh),
-            rx_keymsg,
+            ticked_progress: None,
         }
     }
 
-    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
-        while !self.should_exit {
-            terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
-            sleep(std::time::Duration::from_millis(10));
+    /// App without the dummy thread, each tick advances the progress by the
+    /// step the thread makes every 100 ms.
+    fn scripted() -> Self {
+        let (tx_close, _) = unbounded();
+        let (_, rx_progress) = unbounded();
+        Self {
+            progress_bar_color_idx: 0,
+            progress_name: "Process 1".to_string(),
+            progress_ratio: 0.0,
+            progress_thread_jh: None,
+            tx_close,
+            rx_progress,
+            ticked_progress: Some(0.0),
         }
+    }
+
+    fn stop_progress_thread(&mut self) {
         let _ = self.tx_close.send(true);
-        let _ = self.tx_close_input.send(true);
         if let Some(progress_thread_jh) = self.progress_thread_jh.take() {
             let _ = progress_thread_jh.join();
         }
-        if let Some(input_thread_jh) = self.input_thread_jh.take() {
-            let _ = input_thread_jh.join();
+    }
+}
+
+impl Component for App {
+    fn handle_event(&mut self, event: &AppEvent) -> Result<Option<Action>> {
+        if let AppEvent::Key(key) = event {
+            if key.kind == KeyEventKind::Press
+                && (key.code == KeyCode::Char('q')
+                    || key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL)
+            {
+                return Ok(Some(Action::Quit));
+            } else if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('c') {
+                self.progress_bar_color_idx = self.progress_bar_color_idx.wrapping_add(1);
+            }
         }
-        Ok(())
+        Ok(None)
     }
 
-    fn handle_events(&mut self) -> Result<()> {
-        if let Ok(key_msg) = self.rx_keymsg.try_recv() {
-            match key_msg {
-                KeyMsg::ChangeColor => {
-                    self.progress_bar_color_idx += 1;
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick => {
+                if let Some(progress) = &mut self.ticked_progress {
+                    *progress = Dummy::advance(*progress);
+                    self.progress_ratio = *progress / 100.0;
+                    return Ok(Some(Action::Render));
                 }
-                KeyMsg::Close => {
-                    self.should_exit = true;
+                if let Some(progress_msg) = self.rx_progress.try_iter().last() {
+                    self.progress_ratio = progress_msg / 100.0;
+                    return Ok(Some(Action::Render));
                 }
             }
+            Action::Quit => self.stop_progress_thread(),
+            _ => {}
         }
-        if let Ok(progress_msg) = self.rx_progress.try_recv() {
-            self.progress_ratio = progress_msg / 100.0;
-        }
-        Ok(())
+        Ok(None)
     }
 
-    fn draw(&self, frame: &mut Frame) {
-        frame.render_widget(self, frame.area());
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        frame.render_widget(&*self, area);
     }
 }
 
@@ -202,3 +185,113 @@ impl Widget for &App {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui_examples::script::Headless;
+
+    /// App without the dummy thread, progress is sent through the returned channel.
+    fn app() -> (App, Sender<f64>, Receiver<bool>) {
+        let (tx_close, rx_close) = unbounded();
+        let (tx_progress, rx_progress) = unbounded();
+        let app = App::new(thread::spawn(|| {}), tx_close, rx_progress);
+        (app, tx_progress, rx_close)
+    }
+
+    fn key(code: KeyCode) -> AppEvent {
+        AppEvent::Key(code.into())
+    }
+
+    #[test]
+    fn tick_takes_latest_progress() {
+        let (mut app, tx_progress, _rx_close) = app();
+        assert_eq!(app.update(Action::Tick).unwrap(), None);
+        tx_progress.send(10.0).unwrap();
+        tx_progress.send(25.0).unwrap();
+        assert_eq!(app.update(Action::Tick).unwrap(), Some(Action::Render));
+        assert_eq!(app.progress_ratio, 0.25);
+    }
+
+    #[test]
+    fn keys_change_color_and_quit() {
+        let (mut app, _tx_progress, rx_close) = app();
+        assert_eq!(app.handle_event(&key(KeyCode::Char('c'))).unwrap(), None);
+        assert_eq!(app.progress_bar_color_idx, 1);
+        let quit = app.handle_event(&key(KeyCode::Char('q'))).unwrap();
+        assert_eq!(quit, Some(Action::Quit));
+        app.update(Action::Quit).unwrap();
+        assert_eq!(rx_close.try_recv(), Ok(true));
+        assert!(app.progress_thread_jh.is_none());
+    }
+
+    #[test]
+    fn render_snapshot() {
+        let (mut app, tx_progress, _rx_close) = app();
+        tx_progress.send(50.0).unwrap();
+        app.update(Action::Tick).unwrap();
+        let screen = Headless::new(40, 10).unwrap().screen(&mut app).unwrap();
+        assert_eq!(screen.lines(), SNAPSHOT);
+        // the filled half of the gauge is green
+        assert_eq!(screen.buffer[(1, 3)].fg, Color::Green);
+    }
+
+    const SNAPSHOT: [&str; 10] = [
+        "Progress overview                       ",
+        "                                        ",
+        "┏Progress overview━━━━━━━━━━━━━━━━━━━━━┓",
+        "┃████████████Process 1: 50%            ┃",
+        "┗━━━━━ Quit <Q>  Change color <C> ━━━━━┛",
+        "                                        ",
+        "                                        ",
+        "                                        ",
+        "                                        ",
+        "                                        ",
+    ];
+
+    #[test]
+    fn color_cycles_through_five_colors() {
+        let (mut app, tx_progress, _rx_close) = app();
+        tx_progress.send(100.0).unwrap();
+        app.update(Action::Tick).unwrap();
+        let mut headless = Headless::new(20, 10).unwrap();
+        let mut colors = Vec::new();
+        for _ in 0..6 {
+            colors.push(headless.screen(&mut app).unwrap().buffer[(1, 3)].fg);
+            app.handle_event(&key(KeyCode::Char('c'))).unwrap();
+        }
+        assert_eq!(colors[0], colors[5]);
+        assert_eq!(colors[1], Color::Blue);
+    }
+
+    #[test]
+    fn render_at_several_sizes() {
+        let (mut app, _tx_progress, _rx_close) = app();
+        Headless::new(1, 1).unwrap().screen(&mut app).unwrap();
+        for (width, height) in [(20, 5), (80, 24), (200, 60)] {
+            let screen = Headless::new(width, height)
+                .unwrap()
+                .screen(&mut app)
+                .unwrap();
+            assert!(screen.lines()[0].starts_with("Progress overview"));
+        }
+    }
+
+    #[test]
+    fn script_ticks_advance_the_progress() {
+        let script = "tick 10\ntick 190".parse().unwrap();
+        let snapshots = Headless::new(40, 10)
+            .unwrap()
+            .run(&script, &mut App::scripted())
+            .unwrap();
+        let labels: Vec<_> = snapshots.iter().map(|s| s.lines()[3].clone()).collect();
+        assert_eq!(
+            labels,
+            [
+                "┃            Process 1: 0%             ┃",
+                "┃██          Process 1: 5%             ┃",
+                "┃███████████Process 1: 100% ███████████┃",
+            ]
+        );
+    }
+}
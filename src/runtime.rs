@@ -0,0 +1,278 @@
+use crate::{
+    action::Action,
+    component::Component,
+    event::{AppEvent, CrosstermEvents, EventSource},
+    tui::{resume_tui, suspend_tui},
+};
+use color_eyre::{eyre::WrapErr, Result};
+use ratatui::{
+    layout::{Position, Rect},
+    prelude::{Backend, Terminal},
+};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+/// Event loop shared by the example apps.
+///
+/// Waits for events of an [`EventSource`] until the next tick is due,
+/// emits `Action::Tick` at `tick_rate` and redraws at most `frame_rate` times
+/// per second, and only when an event or an `Action::Render` asked for it.
+/// A closed event source quits like `Action::Quit`.
+pub struct Runtime {
+    tick_rate: f64,
+    frame_rate: f64,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self {
+            tick_rate: 4.0,
+            frame_rate: 30.0,
+        }
+    }
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ticks per second, a rate that is not positive (or NaN) sends no ticks
+    pub fn tick_rate(mut self, tick_rate: f64) -> Self {
+        self.tick_rate = tick_rate;
+        self
+    }
+
+    /// maximum frames per second, a rate that is not positive (or NaN) draws
+    /// whenever asked to
+    pub fn frame_rate(mut self, frame_rate: f64) -> Self {
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    /// Run with the events of the terminal.
+    pub fn run<B: Backend>(
+        &self,
+        terminal: &mut Terminal<B>,
+        component: &mut impl Component,
+    ) -> Result<()> {
+        self.run_with(terminal, &mut CrosstermEvents, component)
+    }
+
+    pub fn run_with<B: Backend>(
+        &self,
+        terminal: &mut Terminal<B>,
+        events: &mut impl EventSource,
+        component: &mut impl Component,
+    ) -> Result<()> {
+        let tick_interval = interval(self.tick_rate);
+        let frame_interval = interval(self.frame_rate).unwrap_or_default();
+        let mut last_tick = Instant::now();
+        let mut last_frame: Option<Instant> = None;
+        let mut needs_render = true;
+        let mut actions = VecDeque::new();
+
+        loop {
+            let frame_due = last_frame.map_or(Instant::now(), |t| t + frame_interval);
+            if needs_render && Instant::now() >= frame_due {
+                terminal.draw(|frame| component.render(frame, frame.area()))?;
+                last_frame = Some(Instant::now());
+                needs_render = false;
+            }
+
+            let mut deadline = match tick_interval {
+                Some(interval) => last_tick + interval,
+                None => Instant::now() + IDLE_TIMEOUT,
+            };
+            if needs_render {
+                deadline = deadline.min(frame_due);
+            }
+            if let Some(event) = events.next(deadline.saturating_duration_since(Instant::now()))? {
+                match event {
+                    AppEvent::Resize(columns, rows) => {
+                        actions.push_back(Action::Resize(columns, rows));
+                    }
+                    AppEvent::Tick => actions.push_back(Action::Tick),
+                    _ => {}
+                }
+                if let Some(action) = component.handle_event(&event)? {
+                    actions.push_back(action);
+                }
+                needs_render = true;
+            } else if events.is_closed() {
+                actions.push_back(Action::Quit);
+            }
+            if tick_interval.is_some_and(|interval| last_tick.elapsed() >= interval) {
+                last_tick = Instant::now();
+                actions.push_back(Action::Tick);
+            }
+
+            while let Some(action) = actions.pop_front() {
+                if let Some(next) = component.update(action.clone())? {
+                    actions.push_back(next);
+                }
+                match action {
+                    Action::Quit => return Ok(()),
+                    Action::Render | Action::Resize(_, _) => needs_render = true,
+                    Action::Execute(command) => {
+                        execute(terminal, &command)?;
+                        needs_render = true;
+                    }
+                    Action::Tick => {}
+                }
+            }
+        }
+    }
+}
+
+/// how long to wait for an event at once without ticks
+const IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time between two ticks or frames at `rate` per second, `None` if the rate
+/// is not positive or so low the interval overflows.
+fn interval(rate: f64) -> Option<Duration> {
+    if rate > 0.0 {
+        Duration::try_from_secs_f64(1.0 / rate).ok()
+    } else {
+        None
+    }
+}
+
+/// Run `command` with `sh -c` on the terminal, suspending the UI meanwhile.
+///
+/// The command reads from and writes to the tty, as stdin and stdout may be
+/// taken by candidates piped in and a selection captured. Its exit status
+/// is ignored, like the one of an editor quit with an error.
+fn execute<B: Backend>(terminal: &mut Terminal<B>, command: &str) -> Result<()> {
+    // an inline viewport is placed anew at the cursor after resuming
+    let top = terminal.get_frame().area().as_position();
+    terminal.set_cursor_position(top)?;
+    let options = suspend_tui()?;
+    let tty = || File::options().read(true).write(true).open("/dev/tty");
+    let status = (|| {
+        Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(tty()?)
+            .stdout(tty()?)
+            .stderr(tty()?)
+            .status()
+    })();
+    if let Some(options) = options {
+        resume_tui(options)?;
+        terminal.resize(Rect::from((Position::ORIGIN, terminal.size()?)))?;
+    }
+    status.wrap_err_with(|| format!("running `{command}`"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::ChannelEvents;
+    use crossterm::event::KeyCode;
+    use ratatui::{backend::TestBackend, Frame};
+    use std::thread;
+
+    /// Records what it got, quits on `q`.
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<AppEvent>,
+        actions: Vec<Action>,
+        ticks: usize,
+    }
+
+    impl Component for Recorder {
+        fn handle_event(&mut self, event: &AppEvent) -> Result<Option<Action>> {
+            self.events.push(event.clone());
+            match event {
+                AppEvent::Key(key) if key.code == KeyCode::Char('q') => Ok(Some(Action::Quit)),
+                _ => Ok(None),
+            }
+        }
+
+        fn update(&mut self, action: Action) -> Result<Option<Action>> {
+            // ticks of the runtime depend on timing
+            match action {
+                Action::Tick => self.ticks += 1,
+                Action::Render => {}
+                action => self.actions.push(action),
+            }
+            Ok(None)
+        }
+
+        fn render(&mut self, frame: &mut Frame, area: Rect) {
+            frame.render_widget(format!("{} events", self.events.len()), area);
+        }
+    }
+
+    fn terminal() -> Terminal<TestBackend> {
+        Terminal::new(TestBackend::new(20, 2)).unwrap()
+    }
+
+    #[test]
+    fn events_are_handled_in_order_until_quit() {
+        let (tx, mut events) = ChannelEvents::new();
+        let custom = AppEvent::Custom("reloaded".to_string());
+        let quit = AppEvent::Key(KeyCode::Char('q').into());
+        for event in [
+            custom.clone(),
+            AppEvent::Resize(30, 4),
+            quit.clone(),
+            custom.clone(),
+        ] {
+            tx.send(event).unwrap();
+        }
+        let mut terminal = terminal();
+        let mut recorder = Recorder::default();
+        Runtime::new()
+            .run_with(&mut terminal, &mut events, &mut recorder)
+            .unwrap();
+        assert_eq!(recorder.events, [custom, AppEvent::Resize(30, 4), quit]);
+        assert_eq!(recorder.actions, [Action::Resize(30, 4), Action::Quit]);
+    }
+
+    #[test]
+    fn closed_source_quits_promptly() {
+        let (tx, mut events) = ChannelEvents::new();
+        let sender = thread::spawn(move || {
+            tx.send(AppEvent::Tick).unwrap();
+            thread::sleep(Duration::from_millis(20));
+        });
+        let started = Instant::now();
+        let mut recorder = Recorder::default();
+        // a tick every 10 seconds would keep a blocking loop waiting
+        Runtime::new()
+            .tick_rate(0.1)
+            .run_with(&mut terminal(), &mut events, &mut recorder)
+            .unwrap();
+        sender.join().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(recorder.events, [AppEvent::Tick]);
+        assert_eq!(recorder.actions, [Action::Quit]);
+    }
+
+    #[test]
+    fn rates_without_an_interval_turn_ticks_and_the_frame_limit_off() {
+        for rate in [0.0, -1.0, f64::NAN, f64::MIN_POSITIVE] {
+            let (tx, mut events) = ChannelEvents::new();
+            let sender = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                tx.send(AppEvent::Key(KeyCode::Char('q').into())).unwrap();
+            });
+            let mut recorder = Recorder::default();
+            Runtime::new()
+                .tick_rate(rate)
+                .frame_rate(rate)
+                .run_with(&mut terminal(), &mut events, &mut recorder)
+                .unwrap();
+            sender.join().unwrap();
+            assert_eq!(recorder.ticks, 0, "{rate}");
+            assert_eq!(recorder.actions, [Action::Quit]);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crate::{action::Action, component::Component, event::AppEvent, key::KeyChord};
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Report, Result,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// One step of a [`Script`], a line of the script file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// `key ctrl-j`, keys are written like in `--bind`
+    Key(KeyChord),
+    /// `type some text`, one key press per char
+    Type(String),
+    /// `resize 80 24`, columns and rows
+    Resize(u16, u16),
+    /// `tick` or `tick 5`
+    Tick(u32),
+}
+
+impl FromStr for Step {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (command, args) = s.split_once(' ').unwrap_or((s, ""));
+        let step = match command {
+            "key" => Step::Key(args.trim().parse()?),
+            "type" if !args.is_empty() => Step::Type(args.to_string()),
+            "resize" => {
+                let size = args.split_once(' ').and_then(|(columns, rows)| {
+                    Some((columns.parse().ok()?, rows.trim().parse().ok()?))
+                });
+                match size {
+                    Some((columns @ 1.., rows @ 1..)) => Step::Resize(columns, rows),
+                    _ => bail!("invalid size {args}, expected `resize COLUMNS ROWS`"),
+                }
+            }
+            "tick" if args.trim().is_empty() => Step::Tick(1),
+            "tick" => Step::Tick(
+                args.trim()
+                    .parse()
+                    .map_err(|_| eyre!("invalid tick count {args}"))?,
+            ),
+            _ => bail!("unknown step {s}, expected key, type, resize or tick"),
+        };
+        Ok(step)
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Key(chord) => write!(f, "key {chord}"),
+            Step::Type(text) => write!(f, "type {text}"),
+            Step::Resize(columns, rows) => write!(f, "resize {columns} {rows}"),
+            Step::Tick(1) => write!(f, "tick"),
+            Step::Tick(count) => write!(f, "tick {count}"),
+        }
+    }
+}
+
+/// Input to drive an app without a terminal, one [`Step`] per line.
+///
+/// ```text
+/// # comments and empty lines are skipped
+/// resize 60 20
+/// type neuro
+/// key down
+/// tick 3
+/// key enter
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    pub steps: Vec<Step>,
+}
+
+impl FromStr for Script {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let steps = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(index, line)| {
+                line.trim_start()
+                    .parse()
+                    .wrap_err_with(|| format!("line {}", index + 1))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { steps })
+    }
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).wrap_err_with(|| format!("reading {}", path.display()))?;
+        content
+            .parse()
+            .wrap_err_with(|| format!("invalid script {}", path.display()))
+    }
+
+    /// Remove `--script FILE` from the command line arguments.
+    pub fn take_arg(args: &mut Vec<String>) -> Result<Option<PathBuf>> {
+        let Some(position) = args.iter().position(|arg| arg == "--script") else {
+            return Ok(None);
+        };
+        if position + 1 == args.len() {
+            bail!("--script expects a file");
+        }
+        let path = args.remove(position + 1);
+        args.remove(position);
+        Ok(Some(PathBuf::from(path)))
+    }
+}
+
+/// The screen after a step of a [`Script`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// the step, or `start` for the first screen
+    pub step: String,
+    pub buffer: Buffer,
+}
+
+impl Snapshot {
+    /// Text of the screen, one string per row.
+    pub fn lines(&self) -> Vec<String> {
+        self.buffer
+            .content
+            .chunks(usize::from(self.buffer.area.width).max(1))
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "-- {}", self.step)?;
+        for line in self.lines() {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a [`Script`] against a component drawn on a [`TestBackend`].
+///
+/// Unlike the [`Runtime`](crate::runtime::Runtime) nothing happens between
+/// steps: ticks are only sent by `tick` steps, and while the component
+/// [is busy](Component::is_busy), so the screens don't depend on timing.
+/// `Action::Execute` is ignored, there is no terminal to hand over.
+pub struct Headless {
+    terminal: Terminal<TestBackend>,
+    timeout: Duration,
+}
+
+impl Headless {
+    pub fn new(columns: u16, rows: u16) -> Result<Self> {
+        Ok(Self {
+            terminal: Terminal::new(TestBackend::new(columns, rows))?,
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// how long to wait for a busy component after each step
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run the steps up to the end or an `Action::Quit`, returning the
+    /// screen at the start and after each step.
+    pub fn run(
+        &mut self,
+        script: &Script,
+        component: &mut impl Component,
+    ) -> Result<Vec<Snapshot>> {
+        let mut snapshots = Vec::with_capacity(script.steps.len() + 1);
+        snapshots.push(self.screen(component)?);
+        for step in &script.steps {
+            let quit = self
+                .step(step, component)
+                .wrap_err_with(|| format!("step {step}"))?;
+            snapshots.push(self.draw(step.to_string(), component)?);
+            if quit {
+                break;
+            }
+        }
+        Ok(snapshots)
+    }
+
+    /// The screen once the component is no longer busy, as step `start`.
+    pub fn screen(&mut self, component: &mut impl Component) -> Result<Snapshot> {
+        self.settle(component)?;
+        self.draw("start".to_string(), component)
+    }
+
+    /// Apply a step, true if the component quit.
+    fn step(&mut self, step: &Step, component: &mut impl Component) -> Result<bool> {
+        let mut quit = false;
+        match step {
+            Step::Key(chord) => {
+                let event = AppEvent::Key(KeyEvent::new(chord.code, chord.modifiers));
+                quit = self.handle_event(&event, component)?;
+            }
+            Step::Type(text) => {
+                for c in text.chars() {
+                    let event = AppEvent::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+                    if self.handle_event(&event, component)? {
+                        return Ok(true);
+                    }
+                    self.settle(component)?;
+                }
+            }
+            Step::Resize(columns, rows) => {
+                self.terminal.backend_mut().resize(*columns, *rows);
+                let event = AppEvent::Resize(*columns, *rows);
+                quit = self.handle_event(&event, component)?;
+                quit |= self.dispatch(Action::Resize(*columns, *rows), component)?;
+            }
+            Step::Tick(count) => {
+                for _ in 0..*count {
+                    if self.dispatch(Action::Tick, component)? {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        if !quit {
+            self.settle(component)?;
+        }
+        Ok(quit)
+    }
+
+    fn handle_event(&mut self, event: &AppEvent, component: &mut impl Component) -> Result<bool> {
+        match component.handle_event(event)? {
+            Some(action) => self.dispatch(action, component),
+            None => Ok(false),
+        }
+    }
+
+    /// Pass an action and the ones it leads to to the component, true on `Action::Quit`.
+    fn dispatch(&mut self, action: Action, component: &mut impl Component) -> Result<bool> {
+        let mut next = Some(action);
+        let mut quit = false;
+        while let Some(action) = next.take() {
+            quit |= action == Action::Quit;
+            next = component.update(action)?;
+        }
+        Ok(quit)
+    }
+
+    /// Tick until the component finished its background work.
+    fn settle(&mut self, component: &mut impl Component) -> Result<()> {
+        let started = Instant::now();
+        while component.is_busy() {
+            if started.elapsed() > self.timeout {
+                bail!("still busy after {:?}", self.timeout);
+            }
+            if self.dispatch(Action::Tick, component)? {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, step: String, component: &mut impl Component) -> Result<Snapshot> {
+        self.terminal
+            .draw(|frame| component.render(frame, frame.area()))?;
+        Ok(Snapshot {
+            step,
+            buffer: self.terminal.backend().buffer().clone(),
+        })
+    }
+}
+
+/// Run the script at `path` headless and print the screen after each step.
+pub fn run_script(path: &Path, component: &mut impl Component) -> Result<()> {
+    let script = Script::load(path)?;
+    let snapshots = Headless::new(80, 24)?.run(&script, component)?;
+    let mut stdout = io::stdout().lock();
+    for snapshot in snapshots {
+        write!(stdout, "{snapshot}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{layout::Rect, Frame};
+
+    /// Shows the keys typed so far, quits on `q`.
+    #[derive(Default)]
+    struct Echo {
+        text: String,
+        ticks: u32,
+    }
+
+    impl Component for Echo {
+        fn handle_event(&mut self, event: &AppEvent) -> Result<Option<Action>> {
+            match event {
+                AppEvent::Key(key) if key.code == KeyCode::Char('q') => Ok(Some(Action::Quit)),
+                AppEvent::Key(key) => {
+                    if let KeyCode::Char(c) = key.code {
+                        self.text.push(c);
+                    }
+                    Ok(Some(Action::Render))
+                }
+                _ => Ok(None),
+            }
+        }
+
+        fn update(&mut self, action: Action) -> Result<Option<Action>> {
+            if action == Action::Tick {
+                self.ticks += 1;
+            }
+            Ok(None)
+        }
+
+        fn render(&mut self, frame: &mut Frame, area: Rect) {
+            let text = format!("{} {}", self.text, self.ticks);
+            frame.render_widget(text, area);
+        }
+    }
+
+    #[test]
+    fn parse_script() {
+        let script: Script = "# login\n\nresize 20 5\n  type ab c\nkey ctrl-j\ntick\ntick 3\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            script.steps,
+            [
+                Step::Resize(20, 5),
+                Step::Type("ab c".to_string()),
+                Step::Key(KeyChord::new(KeyCode::Char('j'), KeyModifiers::CONTROL)),
+                Step::Tick(1),
+                Step::Tick(3),
+            ]
+        );
+        let err = "tick\nresize 0 5".parse::<Script>().unwrap_err();
+        assert_eq!(err.to_string(), "line 2");
+        assert!("key ctrl-nope".parse::<Script>().is_err());
+        assert!("click 1 2".parse::<Script>().is_err());
+    }
+
+    #[test]
+    fn steps_display_as_parsed() {
+        for line in ["key ctrl-j", "type a b", "resize 80 24", "tick", "tick 2"] {
+            assert_eq!(line.parse::<Step>().unwrap().to_string(), line);
+        }
+    }
+
+    #[test]
+    fn take_script_arg() {
+        let mut args = ["--height", "10", "--script", "demo.txt"]
+            .map(String::from)
+            .to_vec();
+        let path = Script::take_arg(&mut args).unwrap();
+        assert_eq!(path, Some(PathBuf::from("demo.txt")));
+        assert_eq!(args, ["--height", "10"]);
+        assert!(Script::take_arg(&mut vec!["--script".to_string()]).is_err());
+    }
+
+    #[test]
+    fn snapshot_after_each_step() {
+        let script = "type ab\ntick 2\nresize 6 2\nkey q\ntype never"
+            .parse()
+            .unwrap();
+        let snapshots = Headless::new(10, 1)
+            .unwrap()
+            .run(&script, &mut Echo::default())
+            .unwrap();
+        let screens: Vec<_> = snapshots
+            .iter()
+            .map(|snapshot| (snapshot.step.as_str(), snapshot.lines()))
+            .collect();
+        assert_eq!(
+            screens,
+            [
+                ("start", vec![" 0        ".to_string()]),
+                ("type ab", vec!["ab 0      ".to_string()]),
+                ("tick 2", vec!["ab 2      ".to_string()]),
+                (
+                    "resize 6 2",
+                    vec!["ab 2  ".to_string(), "      ".to_string()]
+                ),
+                ("key q", vec!["ab 2  ".to_string(), "      ".to_string()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,411 @@
+use color_eyre::{
+    config::HookBuilder,
+    eyre::{self, bail, EyreHandler, WrapErr},
+    Report, Result,
+};
+use crossterm::{
+    cursor::{MoveTo, Show},
+    event::{DisableMouseCapture, EnableMouseCapture},
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+    ExecutableCommand,
+};
+use ratatui::{
+    backend::{self, Backend, WindowSize},
+    buffer::Cell,
+    layout::{Position, Size},
+    prelude::{CrosstermBackend, Terminal},
+    TerminalOptions, Viewport,
+};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufWriter, IsTerminal, Write},
+    ops::{Deref, DerefMut},
+    panic::{self, Location},
+    str::FromStr,
+    sync::Mutex,
+};
+
+/// Stream the terminal UI is drawn on.
+///
+/// Tools printing a result to stdout draw on stderr instead,
+/// so `$(fuzzy_finder)` only captures the selection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Output {
+    #[default]
+    Stdout,
+    Stderr,
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout => io::stdout().write(buf),
+            Output::Stderr => io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout => io::stdout().flush(),
+            Output::Stderr => io::stderr().flush(),
+        }
+    }
+}
+
+/// Height of an inline viewport, `--height 10` or `--height 40%`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Height {
+    Lines(u16),
+    /// percentage of the terminal height
+    Percent(u16),
+}
+
+impl Height {
+    /// Lines of a terminal with `rows` lines, at least one.
+    pub fn lines(self, rows: u16) -> u16 {
+        let lines = match self {
+            Height::Lines(lines) => lines,
+            Height::Percent(percent) => (u32::from(rows) * u32::from(percent) / 100) as u16,
+        };
+        lines.clamp(1, rows.max(1))
+    }
+}
+
+impl FromStr for Height {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let height = match s.strip_suffix('%') {
+            Some(percent) => match percent.parse() {
+                Ok(percent @ 1..=100) => Height::Percent(percent),
+                _ => bail!("invalid height {s}, expected a percentage from 1% to 100%"),
+            },
+            None => match s.parse() {
+                Ok(lines @ 1..) => Height::Lines(lines),
+                _ => bail!("invalid height {s}, expected a number of lines or a percentage"),
+            },
+        };
+        Ok(height)
+    }
+}
+
+/// How the terminal is set up, see [`Tui::with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TuiOptions {
+    pub output: Output,
+    /// draw inline below the prompt instead of on the alternate screen
+    pub height: Option<Height>,
+    /// report mouse clicks and scrolling as events
+    pub mouse: bool,
+}
+
+impl TuiOptions {
+    /// Parse `--height N|N%`, the only option of the simple examples.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--height" => {
+                    let Some(value) = args.next() else {
+                        bail!("{arg} expects a value");
+                    };
+                    options.height = Some(value.parse().wrap_err("parsing --height")?);
+                }
+                _ => bail!("unknown option {arg}, the only option is --height N|N%"),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Terminal state to restore, set while the terminal is in raw mode,
+/// so restoring twice (panic hook + `Drop`) is harmless.
+#[derive(Debug, Clone, Copy)]
+struct ActiveTui {
+    options: TuiOptions,
+    screen: Screen,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Screen {
+    Alternate,
+    /// first line of the viewport, `None` until it is known
+    Inline(Option<u16>),
+}
+
+static ACTIVE_TUI: Mutex<Option<ActiveTui>> = Mutex::new(None);
+
+pub fn init_tui(options: TuiOptions) -> io::Result<Terminal<TuiBackend>> {
+    enter_tui(options)?;
+    let viewport = match options.height {
+        Some(height) => Viewport::Inline(height.lines(terminal::size()?.1)),
+        None => Viewport::Fullscreen,
+    };
+    Terminal::with_options(
+        TuiBackend::new(options.output),
+        TerminalOptions { viewport },
+    )
+}
+
+/// Hand the terminal over to a foreground program like an editor, returning
+/// the options to [`resume_tui`] with afterwards, `None` if it was not set up.
+pub fn suspend_tui() -> io::Result<Option<TuiOptions>> {
+    let options = ACTIVE_TUI
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|active| active.options);
+    restore_tui()?;
+    Ok(options)
+}
+
+/// Set the terminal up again after [`suspend_tui`]. The terminal has to be
+/// resized before the next draw, which places an inline viewport anew.
+pub fn resume_tui(options: TuiOptions) -> io::Result<()> {
+    enter_tui(options)
+}
+
+/// Raw mode, and the alternate screen unless the viewport is inline.
+fn enter_tui(options: TuiOptions) -> io::Result<()> {
+    let mut output = options.output;
+    let screen = match options.height {
+        Some(_) => Screen::Inline(None),
+        None => Screen::Alternate,
+    };
+    if options.height.is_none() {
+        output.execute(EnterAlternateScreen)?;
+    }
+    if options.mouse {
+        output.execute(EnableMouseCapture)?;
+    }
+    enable_raw_mode()?;
+    // only a terminal that was set up completely is restored
+    *ACTIVE_TUI.lock().unwrap_or_else(|e| e.into_inner()) = Some(ActiveTui { options, screen });
+    Ok(())
+}
+
+/// `CrosstermBackend` drawing on an [`Output`], buffered so a frame is
+/// written at once (`Terminal::draw` flushes).
+///
+/// `crossterm::cursor::position`, which places ratatui's inline viewport,
+/// asks for the cursor on stdout. If stdout is redirected, like when the
+/// selection of a picker is captured with `$(..)`, the cursor is moved to
+/// the last row instead, putting the viewport at the bottom of the screen.
+pub struct TuiBackend {
+    inner: CrosstermBackend<BufWriter<Output>>,
+    stdout_is_terminal: bool,
+}
+
+impl TuiBackend {
+    pub fn new(output: Output) -> Self {
+        Self {
+            inner: CrosstermBackend::new(BufWriter::new(output)),
+            stdout_is_terminal: io::stdout().is_terminal(),
+        }
+    }
+}
+
+impl Backend for TuiBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        self.inner.draw(content)
+    }
+
+    fn append_lines(&mut self, n: u16) -> io::Result<()> {
+        self.inner.append_lines(n)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        if self.stdout_is_terminal {
+            return self.inner.get_cursor_position();
+        }
+        let bottom = Position::new(0, self.inner.size()?.height.saturating_sub(1));
+        self.inner.set_cursor_position(bottom)?;
+        Ok(bottom)
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        self.inner.set_cursor_position(position)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn clear_region(&mut self, clear_type: backend::ClearType) -> io::Result<()> {
+        self.inner.clear_region(clear_type)
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        self.inner.size()
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        self.inner.window_size()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Backend::flush(&mut self.inner)
+    }
+}
+
+/// Remember where the inline viewport starts, it moves when the terminal scrolls.
+fn set_inline_top(top: u16) {
+    if let Some(active) = ACTIVE_TUI
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_mut()
+    {
+        active.screen = Screen::Inline(Some(top));
+    }
+}
+
+/// Leave raw mode, and the alternate screen or clear the inline viewport,
+/// so no lines of the UI are left behind.
+pub fn restore_tui() -> io::Result<()> {
+    let active = ACTIVE_TUI.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(ActiveTui { options, screen }) = active {
+        let mut output = options.output;
+        if options.mouse {
+            output.execute(DisableMouseCapture)?;
+        }
+        match screen {
+            Screen::Alternate => {
+                output.execute(LeaveAlternateScreen)?;
+            }
+            Screen::Inline(Some(top)) => {
+                output
+                    .execute(MoveTo(0, top))?
+                    .execute(Clear(ClearType::FromCursorDown))?;
+            }
+            Screen::Inline(None) => {}
+        }
+        output.execute(Show)?;
+        disable_raw_mode()?;
+    }
+    Ok(())
+}
+
+/// Install panic and error hooks which restore the terminal before reporting.
+///
+/// Replaces `color_eyre::install()`. In debug builds panics are reported
+/// with `better_panic`, in release builds with the `color_eyre` panic hook.
+pub fn install_hooks() -> Result<()> {
+    let (panic_hook, eyre_hook) = HookBuilder::default().into_hooks();
+
+    let panic_hook = panic_hook.into_panic_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        let _ = restore_tui();
+        if cfg!(debug_assertions) {
+            better_panic::Settings::auto()
+                .most_recent_first(false)
+                .lineno_suffix(true)
+                .create_panic_handler()(panic_info);
+        } else {
+            panic_hook(panic_info);
+        }
+    }));
+
+    let eyre_hook = eyre_hook.into_eyre_hook();
+    eyre::set_hook(Box::new(move |error| {
+        Box::new(RestoringHandler(eyre_hook(error)))
+    }))?;
+    Ok(())
+}
+
+/// Restores the terminal when a report is printed. The hook itself runs on
+/// every `Report` created, also for errors the app handles and carries on.
+struct RestoringHandler(Box<dyn EyreHandler>);
+
+impl EyreHandler for RestoringHandler {
+    fn debug(&self, error: &(dyn Error + 'static), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let _ = restore_tui();
+        self.0.debug(error, f)
+    }
+
+    fn display(&self, error: &(dyn Error + 'static), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.display(error, f)
+    }
+
+    fn track_caller(&mut self, location: &'static Location<'static>) {
+        self.0.track_caller(location);
+    }
+}
+
+/// Terminal guard: enters raw mode and the alternate screen, or an inline
+/// viewport, on creation and restores the terminal when dropped, even if
+/// the app returned early.
+pub struct Tui {
+    terminal: Terminal<TuiBackend>,
+    inline: bool,
+}
+
+impl Tui {
+    pub fn new() -> io::Result<Self> {
+        Self::with_options(TuiOptions::default())
+    }
+
+    pub fn with_output(output: Output) -> io::Result<Self> {
+        Self::with_options(TuiOptions {
+            output,
+            ..TuiOptions::default()
+        })
+    }
+
+    pub fn with_options(options: TuiOptions) -> io::Result<Self> {
+        let mut tui = Self {
+            terminal: init_tui(options)?,
+            inline: options.height.is_some(),
+        };
+        tui.update_inline_top();
+        Ok(tui)
+    }
+
+    /// Restore the terminal explicitly, reporting errors `Drop` would swallow.
+    pub fn restore(mut self) -> io::Result<()> {
+        self.update_inline_top();
+        restore_tui()
+    }
+
+    fn update_inline_top(&mut self) {
+        if self.inline {
+            set_inline_top(self.terminal.get_frame().area().y);
+        }
+    }
+}
+
+impl Deref for Tui {
+    type Target = Terminal<TuiBackend>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl DerefMut for Tui {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        self.update_inline_top();
+        let _ = restore_tui();
+    }
+}